@@ -0,0 +1,499 @@
+use std::{
+  collections::HashMap,
+  io::{self, BufRead, BufReader, Read, Write},
+  net::{SocketAddr, TcpListener, TcpStream},
+  sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex},
+  thread,
+  time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use crate::{app::{load_bank_items, AnalysisData, QuizItem}, assets};
+
+// API HTTP local (feature "http-api") para los scripts del LMS y otras
+// interfaces. Es un servidor HTTP/1.1 mínimo sobre std: atiende una petición
+// por conexión, cada una en su propio hilo, y siempre responde JSON.
+//
+//   GET  /bancos                         bancos incluidos y los de la carpeta de assets
+//   GET  /bancos/{banco}/unidades        unidades temáticas del banco
+//   GET  /bancos/{banco}/preguntas       reactivos completos (?unidad=...)
+//   POST /sesiones                       {"banco", "unidades", "semilla", "preguntas"}
+//   GET  /sesiones/{id}/pregunta         siguiente pregunta, sin la clave correcta
+//   POST /sesiones/{id}/respuesta        {"clave": "a"}
+//   GET  /sesiones/{id}/resultados       AnalysisData y detalle de respuestas
+//
+// Una sesión terminada se borra al entregar sus resultados; las que se dejan
+// a medias se borran tras SESSION_IDLE_TIMEOUT sin usarse.
+
+pub const DEFAULT_PORT: u16 = 8787;
+const BANKS_DIR: &str = "data/";
+const DEFAULT_BANK: &str = "questions";
+// Los bancos y respuestas caben de sobra; lo demás se rechaza con 413
+const MAX_BODY: usize = 1024 * 1024;
+const MAX_HEADERS: u64 = 16 * 1024;
+// Sesiones abiertas a la vez; con más se responde 503 hasta que expiren
+const MAX_SESSIONS: usize = 1024;
+const SESSION_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Deserialize)]
+struct NewSession {
+  #[serde(default = "default_bank")]
+  banco: String,
+  #[serde(default)]
+  unidades: Vec<String>,
+  semilla: Option<u64>,
+  preguntas: Option<usize>,
+}
+
+fn default_bank() -> String {
+  DEFAULT_BANK.to_owned()
+}
+
+#[derive(Deserialize)]
+struct AnswerRequest {
+  clave: String,
+}
+
+#[derive(Serialize)]
+struct SessionAnswer {
+  pregunta: String,
+  unidad: String,
+  clave: String,
+  respuesta_correcta: String,
+  correcta: bool,
+}
+
+struct Session {
+  bank: String,
+  items: Vec<QuizItem>,
+  next: usize,
+  data: AnalysisData,
+  answers: Vec<SessionAnswer>,
+  last_used: Instant,
+}
+
+impl Session {
+  fn current(&self) -> Option<&QuizItem> {
+    self.items.get(self.next)
+  }
+
+  fn is_finished(&self) -> bool {
+    self.next >= self.items.len()
+  }
+}
+
+struct ApiError {
+  status: u16,
+  message: String,
+}
+
+impl ApiError {
+  fn new(status: u16, message: impl Into<String>) -> Self {
+    ApiError { status, message: message.into() }
+  }
+}
+
+#[derive(Default)]
+struct ApiState {
+  sessions: HashMap<u32, Session>,
+  next_id: u32,
+}
+
+struct Request {
+  method: String,
+  path: String,
+  query: HashMap<String, String>,
+  body: Vec<u8>,
+}
+
+pub struct ApiServer {
+  shutdown: Arc<AtomicBool>,
+  local_addr: SocketAddr,
+}
+
+impl ApiServer {
+  // Solo escucha en la máquina local: la API no tiene autenticación
+  pub fn start(port: u16) -> io::Result<Self> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+    let shutdown = Arc::new(AtomicBool::new(false));
+
+    let accept_shutdown = Arc::clone(&shutdown);
+    thread::spawn(move || {
+      let state = Arc::new(Mutex::new(ApiState::default()));
+      while !accept_shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+          Ok((stream, _)) => {
+            // Una conexión lenta o inactiva no detiene a las demás
+            let state = Arc::clone(&state);
+            thread::spawn(move || {
+              let _ = handle_connection(stream, &state);
+            });
+          }
+          Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+      }
+    });
+
+    Ok(ApiServer { shutdown, local_addr })
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.local_addr
+  }
+}
+
+impl Drop for ApiServer {
+  fn drop(&mut self) {
+    self.shutdown.store(true, Ordering::Relaxed);
+  }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &Mutex<ApiState>) -> io::Result<()> {
+  stream.set_nonblocking(false)?;
+  stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+  stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+
+  // El estado solo se bloquea mientras se atiende la ruta, no al leer o escribir
+  let (status, body) = match read_request(&stream) {
+    // Si otra conexión falló a medias, el estado sigue siendo utilizable
+    Ok(request) => match route(&mut state.lock().unwrap_or_else(|e| e.into_inner()), &request) {
+      Ok(body) => (200, body),
+      Err(e) => (e.status, json!({ "error": e.message })),
+    },
+    Err(e) => (e.status, json!({ "error": e.message })),
+  };
+  write_response(&mut stream, status, &body)
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, ApiError> {
+  let invalid = |e: io::Error| ApiError::new(400, format!("Petición inválida: {}", e));
+  let mut reader = BufReader::new(stream);
+  // Los encabezados se leen con un límite para que una línea infinita no
+  // se coma la memoria
+  let mut head = (&mut reader).take(MAX_HEADERS);
+  let mut request_line = String::new();
+  head.read_line(&mut request_line).map_err(invalid)?;
+  let mut parts = request_line.split_whitespace();
+  let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+    return Err(ApiError::new(400, "Petición inválida: línea de petición incompleta"));
+  };
+
+  let mut content_length = 0;
+  loop {
+    let mut header = String::new();
+    if head.read_line(&mut header).map_err(invalid)? == 0 {
+      return Err(ApiError::new(400, "Petición inválida: encabezados incompletos"));
+    }
+    let header = header.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = header.split_once(':') {
+      if name.eq_ignore_ascii_case("content-length") {
+        content_length = value.trim().parse()
+          .map_err(|_| ApiError::new(400, "Petición inválida: Content-Length inválido"))?;
+      }
+    }
+  }
+  if content_length > MAX_BODY {
+    return Err(ApiError::new(413, format!("El cuerpo no puede pasar de {} bytes", MAX_BODY)));
+  }
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body).map_err(invalid)?;
+
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+  let query = query.split('&')
+    .filter_map(|pair| pair.split_once('='))
+    .map(|(key, value)| (percent_decode(key), percent_decode(value)))
+    .collect();
+
+  Ok(Request {
+    method: method.to_owned(),
+    path: path.to_owned(),
+    query,
+    body,
+  })
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &Value) -> io::Result<()> {
+  let reason = match status {
+    200 => "OK",
+    400 => "Bad Request",
+    404 => "Not Found",
+    413 => "Payload Too Large",
+    503 => "Service Unavailable",
+    _ => "Internal Server Error",
+  };
+  let body = serde_json::to_vec(body)?;
+  write!(
+    stream,
+    "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+    status, reason, body.len()
+  )?;
+  stream.write_all(&body)
+}
+
+// Las unidades llevan espacios y acentos, así que llegan codificadas en la URL
+fn percent_decode(text: &str) -> String {
+  let bytes = text.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'+' => decoded.push(b' '),
+      b'%' if i + 2 < bytes.len() => {
+        let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+          Some(byte) => {
+            decoded.push(byte);
+            i += 2;
+          }
+          None => decoded.push(b'%'),
+        }
+      }
+      byte => decoded.push(byte),
+    }
+    i += 1;
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn route(state: &mut ApiState, request: &Request) -> Result<Value, ApiError> {
+  let segments: Vec<String> = request.path.split('/')
+    .filter(|segment| !segment.is_empty())
+    .map(percent_decode)
+    .collect();
+  let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+  match (request.method.as_str(), segments.as_slice()) {
+    ("GET", ["bancos"]) => Ok(json!(list_banks())),
+    ("GET", ["bancos", bank, "unidades"]) => {
+      let mut units: Vec<String> = Vec::new();
+      for item in load_bank(bank)? {
+        if !units.contains(&item.unidad_tematica) {
+          units.push(item.unidad_tematica);
+        }
+      }
+      units.sort();
+      Ok(json!(units))
+    }
+    ("GET", ["bancos", bank, "preguntas"]) => {
+      let unit = request.query.get("unidad");
+      let items: Vec<QuizItem> = load_bank(bank)?
+        .into_iter()
+        .filter(|item| unit.is_none_or(|unit| item.unidad_tematica == *unit))
+        .collect();
+      Ok(json!(items))
+    }
+    ("POST", ["sesiones"]) => create_session(state, &request.body),
+    ("GET", ["sesiones", id, "pregunta"]) => {
+      let session = find_session(state, id)?;
+      // La clave correcta no se envía: se revela al contestar
+      let question = session.current().map(|item| {
+        let mut item = item.clone();
+        item.respuesta_correcta.clear();
+        item.explicacion = None;
+        item
+      });
+      Ok(json!({
+        "numero": session.next + 1,
+        "total": session.items.len(),
+        "terminada": session.is_finished(),
+        "pregunta": question,
+      }))
+    }
+    ("POST", ["sesiones", id, "respuesta"]) => {
+      let answer: AnswerRequest = parse_body(&request.body)?;
+      let session = find_session(state, id)?;
+      let Some(item) = session.current().cloned() else {
+        return Err(ApiError::new(400, "La sesión ya terminó"));
+      };
+      let correct = answer.clave == item.respuesta_correcta;
+      session.data.record_answer(correct);
+      session.answers.push(SessionAnswer {
+        pregunta: item.pregunta,
+        unidad: item.unidad_tematica,
+        clave: answer.clave,
+        respuesta_correcta: item.respuesta_correcta.clone(),
+        correcta: correct,
+      });
+      session.next += 1;
+      Ok(json!({
+        "correcta": correct,
+        "respuesta_correcta": item.respuesta_correcta,
+        "explicacion": item.explicacion,
+        "terminada": session.is_finished(),
+      }))
+    }
+    ("GET", ["sesiones", id, "resultados"]) => {
+      let session = find_session(state, id)?;
+      let finished = session.is_finished();
+      let results = json!({
+        "banco": session.bank,
+        "terminada": finished,
+        "resultados": session.data,
+        "respuestas": session.answers,
+      });
+      // Ya no se puede contestar nada más, así que no hace falta guardarla
+      if let (true, Ok(id)) = (finished, id.parse::<u32>()) {
+        state.sessions.remove(&id);
+      }
+      Ok(results)
+    }
+    _ => Err(ApiError::new(404, format!("No existe {} {}", request.method, request.path))),
+  }
+}
+
+fn create_session(state: &mut ApiState, body: &[u8]) -> Result<Value, ApiError> {
+  let settings: NewSession = if body.is_empty() { parse_body(b"{}")? } else { parse_body(body)? };
+  let mut items: Vec<QuizItem> = load_bank(&settings.banco)?
+    .into_iter()
+    .filter(|item| settings.unidades.is_empty() || settings.unidades.contains(&item.unidad_tematica))
+    .collect();
+  if items.is_empty() {
+    return Err(ApiError::new(400, "No hay preguntas para esas unidades"));
+  }
+  remove_idle_sessions(state, Instant::now());
+  if state.sessions.len() >= MAX_SESSIONS {
+    return Err(ApiError::new(503, "Hay demasiadas sesiones abiertas; intenta más tarde"));
+  }
+
+  let mut rng = match settings.semilla {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_entropy(),
+  };
+  items.shuffle(&mut rng);
+  if let Some(count) = settings.preguntas {
+    items.truncate(count.max(1));
+  }
+
+  state.next_id += 1;
+  let id = state.next_id;
+  let total = items.len();
+  state.sessions.insert(id, Session {
+    bank: settings.banco,
+    items,
+    next: 0,
+    data: AnalysisData::default(),
+    answers: Vec::new(),
+    last_used: Instant::now(),
+  });
+  Ok(json!({ "id": id, "total": total }))
+}
+
+fn remove_idle_sessions(state: &mut ApiState, now: Instant) {
+  state.sessions.retain(|_, session| now.duration_since(session.last_used) < SESSION_IDLE_TIMEOUT);
+}
+
+fn find_session<'a>(state: &'a mut ApiState, id: &str) -> Result<&'a mut Session, ApiError> {
+  let session = id.parse()
+    .ok()
+    .and_then(|id: u32| state.sessions.get_mut(&id))
+    .ok_or_else(|| ApiError::new(404, format!("No existe la sesión {}", id)))?;
+  session.last_used = Instant::now();
+  Ok(session)
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, ApiError> {
+  serde_json::from_slice(body).map_err(|e| ApiError::new(400, format!("JSON inválido: {}", e)))
+}
+
+fn list_banks() -> Vec<String> {
+  assets::list(BANKS_DIR)
+    .into_iter()
+    .filter_map(|name| name.strip_prefix(BANKS_DIR)?.strip_suffix(".json").map(str::to_owned))
+    .collect()
+}
+
+fn load_bank(name: &str) -> Result<Vec<QuizItem>, ApiError> {
+  // Solo se aceptan nombres listados, así no se puede salir de la carpeta data
+  if !list_banks().iter().any(|bank| bank == name) {
+    return Err(ApiError::new(404, format!("No existe el banco {}", name)));
+  }
+  let path = format!("{}{}.json", BANKS_DIR, name);
+  load_bank_items(&path).map_err(|e| ApiError::new(500, format!("El banco {} no es válido: {}", name, e)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn send(server: &ApiServer, request: &str) -> String {
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+  }
+
+  #[test]
+  fn oversized_body_is_rejected() {
+    let server = ApiServer::start(0).unwrap();
+    let response = send(&server, "POST /sesiones HTTP/1.1\r\nContent-Length: 99999999999\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 413"), "{}", response);
+
+    // El servidor sigue atendiendo después del rechazo
+    let response = send(&server, "GET /bancos HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+  }
+
+  #[test]
+  fn idle_connection_does_not_block_others() {
+    let server = ApiServer::start(0).unwrap();
+    let _idle = TcpStream::connect(server.local_addr()).unwrap();
+    std::thread::sleep(Duration::from_millis(100));
+
+    let start = std::time::Instant::now();
+    let response = send(&server, "GET /bancos HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(start.elapsed() < Duration::from_secs(2));
+  }
+
+  #[test]
+  fn session_round_trip() {
+    let server = ApiServer::start(0).unwrap();
+    let body = r#"{"semilla": 1, "preguntas": 1}"#;
+    let response = send(&server, &format!("POST /sesiones HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body));
+    assert!(response.contains(r#""id":1"#), "{}", response);
+
+    let response = send(&server, "GET /sesiones/1/pregunta HTTP/1.1\r\n\r\n");
+    assert!(response.contains(r#""Respuesta correcta":"""#), "{}", response);
+
+    let body = r#"{"clave": "zz"}"#;
+    let response = send(&server, &format!("POST /sesiones/1/respuesta HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body));
+    assert!(response.contains(r#""correcta":false"#) && response.contains(r#""terminada":true"#), "{}", response);
+
+    // Al entregar los resultados de una sesión terminada se borra
+    let response = send(&server, "GET /sesiones/1/resultados HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200") && response.contains(r#""terminada":true"#), "{}", response);
+    let response = send(&server, "GET /sesiones/1/resultados HTTP/1.1\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 404"), "{}", response);
+  }
+
+  #[test]
+  fn idle_sessions_expire_and_open_ones_are_capped() {
+    let mut state = ApiState::default();
+    for _ in 0..MAX_SESSIONS {
+      assert!(create_session(&mut state, b"").is_ok());
+    }
+    assert_eq!(create_session(&mut state, b"").err().map(|e| e.status), Some(503));
+
+    // Una sesión usada hace poco sobrevive; las abandonadas se borran
+    state.sessions.get_mut(&1).unwrap().last_used += SESSION_IDLE_TIMEOUT;
+    remove_idle_sessions(&mut state, Instant::now() + SESSION_IDLE_TIMEOUT);
+    assert_eq!(state.sessions.keys().collect::<Vec<_>>(), [&1]);
+    assert!(create_session(&mut state, b"").is_ok());
+  }
+
+  #[test]
+  fn percent_decoding() {
+    assert_eq!(percent_decode("1%20-%20Fundamentos"), "1 - Fundamentos");
+    assert_eq!(percent_decode("simulaci%C3%B3n+y"), "simulación y");
+    assert_eq!(percent_decode("100%"), "100%");
+    assert_eq!(percent_decode("%zz"), "%zz");
+  }
+}
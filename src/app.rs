@@ -1,29 +1,215 @@
-use crate::components;
-use std::{collections::HashMap, time::{Duration, Instant}};
+use crate::{assets::{self, AssetError}, classroom::{Classroom, ClassroomHost, ClassroomStudent, LanSettings}, components, daily::DailyDate, duel::{Duel, DuelClient, DuelHost, DuelMatch, DuelSettings, DuelSnapshot, DuelStatus}, editor::BankEditor, import, exam::{ExamSettings, ExamState}, profile::{DailyResult, Profiles}, raid::{Raid, RaidClient, RaidHost, RaidSettings, RaidSnapshot, RaidStatus}, results::AnswerRecord};
+use std::{collections::HashMap, time::Duration};
 use egui::{Align, CentralPanel, Color32, Image, Layout, RichText, SidePanel, TopBottomPanel};
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use web_time::Instant;
 
 const WHITE: Color32 = egui::Color32::WHITE;
 
 pub struct App {
   screen: CurrentScreen,
+  pub mode: GameMode,
   pub quiz: Quiz,
+  pub players: Vec<Player>,
+  pub turn: usize,
+  pub rnd_animation: RndNumberAnimation,
+  pub profiles: Profiles,
+  pub player_name: String,
+  pub hot_seat: HotSeatSettings,
+  pub practice: PracticeSettings,
+  pub exam_settings: ExamSettings,
+  pub exam: ExamState,
+  pub feedback: Option<AnswerFeedback>,
+  pub answer_log: Vec<AnswerRecord>,
+  pub bank: Vec<QuizItem>,
+  pub lan: LanSettings,
+  pub classroom: Classroom,
+  pub raid_settings: RaidSettings,
+  pub raid: Raid,
+  pub raid_playing: bool,
+  pub duel_settings: DuelSettings,
+  pub duel: Duel,
+  pub editor: BankEditor,
+  pub asset_errors: Vec<AssetError>,
+  pub bank_watcher: assets::FileWatcher,
+  pub notice: Option<Notice>,
+}
+
+const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+// Aviso temporal que se dibuja encima de cualquier pantalla
+pub struct Notice {
+  pub text: String,
+  pub is_error: bool,
+  pub shown_at: Instant,
+}
+
+impl Notice {
+  pub fn new(text: impl Into<String>, is_error: bool) -> Self {
+    Notice { text: text.into(), is_error, shown_at: Instant::now() }
+  }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+  Normal,
+  RetoDiario(DailyDate),
+  Infinito,
+  Practica,
+  Examen,
+  HotSeat,
+}
+
+impl GameMode {
+  pub fn label(&self) -> &'static str {
+    match self {
+      GameMode::Normal => "Normal",
+      GameMode::RetoDiario(_) => "Reto diario",
+      GameMode::Infinito => "Modo infinito",
+      GameMode::Practica => "Modo práctica",
+      GameMode::Examen => "Simulacro de examen",
+      GameMode::HotSeat => "Multijugador local",
+    }
+  }
+
+  // La práctica y el examen no tienen vida ni dado
+  pub fn has_health(&self) -> bool {
+    !matches!(self, GameMode::Practica | GameMode::Examen)
+  }
+}
+
+pub struct Player {
+  pub name: String,
   pub health: HealthStatus,
   pub session_data: AnalysisData,
-  pub rnd_animation: RndNumberAnimation,
-  pub streak: StreakState
+  pub streak: StreakState,
+}
+
+impl Player {
+  pub fn new(name: &str) -> Self {
+    Player {
+      name: name.to_owned(),
+      health: HealthStatus::default(),
+      session_data: AnalysisData::default(),
+      streak: StreakState::default(),
+    }
+  }
+
+  pub fn is_alive(&self) -> bool {
+    self.health.hero_health > 0.0
+  }
+
+  // Reglas de una partida con vida, compartidas por la versión gráfica y la
+  // de terminal. Un error rompe la racha y el enemigo golpea; un acierto se
+  // completa con `resolve_roll` cuando termina el dado.
+  pub fn resolve_answer(&mut self, correct: bool) {
+    self.session_data.record_answer(correct);
+    if !correct {
+      self.streak = StreakState::NoStreak;
+      self.health.damage_hero();
+    }
+  }
+
+  // Se acabó el tiempo sin contestar
+  pub fn resolve_timeout(&mut self) {
+    self.health.damage_hero();
+  }
+
+  // Daño del acierto: el dado por el multiplicador de la racha con la que se
+  // contestó. Quien lo recibe depende del modo, así que solo se devuelve.
+  pub fn resolve_roll(&mut self, rolled: usize) -> usize {
+    let damage = rolled * self.streak.multiplier();
+    self.session_data.best_hit = self.session_data.best_hit.max(damage.min(u8::MAX as usize) as u8);
+    self.streak = StreakState::from_streak(self.session_data.win_streak.1);
+    damage
+  }
+}
+
+pub struct HotSeatSettings {
+  pub names: [String; 4],
+  pub num_players: usize,
+  pub versus: bool, // true: los jugadores se atacan entre sí, false: enemigo compartido
+}
+
+impl Default for HotSeatSettings {
+  fn default() -> Self {
+    HotSeatSettings {
+      names: ["Jugador 1", "Jugador 2", "Jugador 3", "Jugador 4"].map(String::from),
+      num_players: 2,
+      versus: false,
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct PracticeSettings {
+  pub units: Vec<(String, bool)>,
+  pub types: Vec<(String, bool)>,
+  pub missed: Vec<QuizItem>,
+}
+
+impl PracticeSettings {
+  fn from_bank(quiz_items: &[QuizItem]) -> Self {
+    let mut units: Vec<(String, bool)> = Vec::new();
+    let mut types: Vec<(String, bool)> = Vec::new();
+    for item in quiz_items {
+      if !units.iter().any(|(unit, _)| *unit == item.unidad_tematica) {
+        units.push((item.unidad_tematica.clone(), true));
+      }
+      if !types.iter().any(|(tipo, _)| *tipo == item.tipo_reactivo) {
+        types.push((item.tipo_reactivo.clone(), true));
+      }
+    }
+    units.sort();
+    types.sort();
+
+    PracticeSettings {
+      units,
+      types,
+      missed: Vec::new(),
+    }
+  }
+
+  pub fn selected_items(&self, quiz_items: &[QuizItem]) -> Vec<QuizItem> {
+    quiz_items.iter()
+      .filter(|item| self.units.iter().any(|(unit, on)| *on && *unit == item.unidad_tematica))
+      .filter(|item| self.types.iter().any(|(tipo, on)| *on && *tipo == item.tipo_reactivo))
+      .cloned()
+      .collect()
+  }
+}
+
+#[derive(Clone)]
+pub struct AnswerFeedback {
+  pub correct: bool,
+  pub correct_answer: String,
+  pub explicacion: Option<String>,
 }
 
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum StreakState {
   X2,
   X3,
+  #[default]
   NoStreak
 }
 
-impl Default for StreakState {
-  fn default() -> Self {
-    Self::NoStreak
+impl StreakState {
+  pub fn from_streak(streak: u16) -> Self {
+    match streak {
+      streak if (3..5).contains(&streak) => StreakState::X2,
+      streak if streak >= 5 => StreakState::X3,
+      _ => StreakState::NoStreak
+    }
+  }
+
+  pub fn multiplier(&self) -> usize {
+    match self {
+      StreakState::NoStreak => 1,
+      StreakState::X2 => 2,
+      StreakState::X3 => 3,
+    }
   }
 }
 
@@ -32,183 +218,2088 @@ pub struct RndNumberAnimation {
   pub duration: Duration,
   pub is_animating: bool,
   pub rnd_number: usize,
+  pub rolled_number: usize,
+  pub rng: StdRng,
 }
 
 impl Default for RndNumberAnimation {
   fn default() -> Self {
+    Self::with_rng(StdRng::from_entropy())
+  }
+}
+
+impl RndNumberAnimation {
+  pub fn from_seed(seed: u64) -> Self {
+    // Semilla distinta a la del quiz para que los dados no dependan de
+    // cuántas preguntas se han contestado mal
+    Self::with_rng(StdRng::seed_from_u64(seed ^ 0xD1CE))
+  }
+
+  fn with_rng(rng: StdRng) -> Self {
     RndNumberAnimation {
       animation_start: None,
       duration: Duration::from_secs(2),
       is_animating: false,
       rnd_number: 0,
+      rolled_number: 0,
+      rng,
     }
   }
+
+  pub fn start(&mut self) {
+    self.rolled_number = self.rng.gen_range(1..=10);
+    self.is_animating = true;
+    self.animation_start = Some(Instant::now());
+  }
 }
 
+#[derive(Default, Serialize)]
 pub struct AnalysisData {
-  pub correct_answers: u8,
-  pub wrong_answers: u8,
-  pub win_streak: (u8, u8), // (best streak, current streak)
-  pub total_quiz: u8,
-  pub best_hit: u8
+  pub correct_answers: u16,
+  pub wrong_answers: u16,
+  pub win_streak: (u16, u16), // (best streak, current streak)
+  pub total_quiz: u16,
+  pub best_hit: u8,
+  pub floors_cleared: u16,
+}
+
+impl AnalysisData {
+  pub fn get_hit_percentage(&self) -> f32 {
+    (self.correct_answers as f32 / self.total_quiz as f32) * 100.0
+  }
+
+  pub fn record_answer(&mut self, correct: bool) {
+    self.total_quiz += 1;
+    if correct {
+      self.correct_answers += 1;
+      self.win_streak.1 += 1;
+      self.win_streak.0 = self.win_streak.0.max(self.win_streak.1);
+    } else {
+      self.wrong_answers += 1;
+      self.win_streak.1 = 0;
+    }
+  }
+}
+
+const MAX_USED_QUIZ_ITEMS: usize = 40;
+
+pub struct Quiz {
+  pub quiz_items: Vec<QuizItem>,
+  pub current_quiz: QuizItem,
+  // Índices de las preguntas ya hechas, para no repetirlas
+  pub used_quiz_items: Vec<usize>,
+  pub duration: Duration,
+  pub start_time: Instant,
+  // Cuándo apareció la pregunta actual; en el examen `start_time` es el del examen completo
+  pub asked_at: Instant,
+  pub rng: StdRng,
+}
+
+impl Quiz {
+  pub fn from_seed(quiz_items: Vec<QuizItem>, seed: u64) -> Self {
+    Self::with_items(quiz_items, StdRng::seed_from_u64(seed))
+  }
+
+  pub fn from_items(quiz_items: Vec<QuizItem>) -> Self {
+    Self::with_items(quiz_items, StdRng::from_entropy())
+  }
+
+  fn with_items(quiz_items: Vec<QuizItem>, mut rng: StdRng) -> Self {
+    // Sin preguntas no se puede jugar; la pantalla de error explica por qué
+    let first_idx = if quiz_items.is_empty() { 0 } else { rng.gen_range(0..quiz_items.len()) };
+    let quiz = quiz_items.get(first_idx).cloned().unwrap_or_default();
+    let used_quiz_items = if quiz_items.is_empty() { Vec::new() } else { vec![first_idx] };
+    let duration = quiz.time_limit();
+
+    Quiz {
+      quiz_items,
+      current_quiz: quiz,
+      used_quiz_items,
+      duration,
+      start_time: Instant::now(),
+      asked_at: Instant::now(),
+      rng,
+    }
+  }
+
+  pub fn remaining(&self) -> Duration {
+    self.duration.saturating_sub(self.start_time.elapsed())
+  }
+
+  pub fn next_question(&mut self) {
+    let new_quiz = self.unused_index().unwrap_or(0);
+    self.current_quiz = self.quiz_items[new_quiz].clone();
+
+    // Las preguntas de hace más de MAX_USED_QUIZ_ITEMS turnos se vuelven a permitir
+    if self.used_quiz_items.len() >= MAX_USED_QUIZ_ITEMS {
+      self.used_quiz_items.clear();
+    }
+    self.used_quiz_items.push(new_quiz);
+
+    self.duration = self.current_quiz.time_limit();
+    self.start_time = Instant::now();
+    self.asked_at = Instant::now();
+  }
+
+  // Cambia las preguntas a media partida. Si la actual sigue en el banco
+  // nuevo se conserva con sus cambios y sin reiniciar el temporizador
+  pub fn replace_items(&mut self, quiz_items: Vec<QuizItem>) {
+    let current = quiz_items.iter().position(|item| item.pregunta == self.current_quiz.pregunta);
+    self.quiz_items = quiz_items;
+    match current {
+      Some(idx) => {
+        self.current_quiz = self.quiz_items[idx].clone();
+        self.duration = self.current_quiz.time_limit();
+        self.used_quiz_items = vec![idx];
+      }
+      None if self.quiz_items.is_empty() => self.current_quiz = QuizItem::default(),
+      None => {
+        // Los índices viejos ya no sirven; se empieza sin preguntas usadas
+        self.used_quiz_items.clear();
+        self.next_question();
+      }
+    }
+  }
+
+  fn unused_index(&mut self) -> Option<usize> {
+    let available_indices: Vec<usize> = (0..self.quiz_items.len())
+      .filter(|index| !self.used_quiz_items.contains(index))
+      .collect();
+
+    // Con el banco agotado (p. ej. en el modo infinito) se vuelven a permitir
+    // todas las preguntas excepto la actual
+    if available_indices.is_empty() {
+      if self.quiz_items.len() <= 1 {
+        return None;
+      }
+      let current = self.used_quiz_items.last().copied();
+      self.used_quiz_items = current.into_iter().collect();
+      return self.unused_index();
+    }
+
+    Some(available_indices[self.rng.gen_range(0..available_indices.len())])
+  }
+
+}
+
+pub struct HealthStatus {
+  pub enemy_health: f32,
+  pub hero_health: f32,
+  pub enemy_max_hp: f32,
+  pub enemy_damage: f32,
+}
+
+impl Default for HealthStatus {
+  fn default() -> Self {
+    HealthStatus {
+      enemy_health: 1.0, 
+      hero_health: 1.0,
+      enemy_max_hp: 1.0,
+      enemy_damage: 0.1,
+    }
+  }
+}
+
+impl HealthStatus {
+  pub fn damage_hero(&mut self) {
+    self.hero_health -= self.enemy_damage;
+    self.hero_health = self.hero_health.clamp(0.0, 1.0);
+  }
+
+  // `damage` está en la escala del enemigo base; los enemigos con más vida
+  // reciben proporcionalmente menos daño en su barra
+  pub fn damage_enemy(&mut self, damage: f32) {
+    self.enemy_health -= damage / self.enemy_max_hp;
+    self.enemy_health = self.enemy_health.clamp(0.0, 1.0);
+  }
+
+  // Cada piso del modo infinito trae un enemigo con más vida y más daño
+  pub fn spawn_enemy(&mut self, floor: u16) {
+    self.enemy_health = 1.0;
+    self.enemy_max_hp = 1.0 + 0.25 * floor as f32;
+    self.enemy_damage = (0.1 + 0.02 * floor as f32).min(0.5);
+  }
+}
+
+// El banco del juego: questions.json más los archivos Aiken de la carpeta
+// data. Un error en questions.json impide jugar; de los Aiken solo se pierden
+// las preguntas con errores, que vuelven como avisos junto con el banco.
+pub fn load_quiz_items() -> Result<(Vec<QuizItem>, Vec<AssetError>), AssetError> {
+  let mut items = load_bank_items(assets::QUESTIONS)?;
+  let (aiken, warnings) = load_aiken_items();
+  items.extend(aiken);
+  Ok((items, warnings))
+}
+
+pub fn load_aiken_items() -> (Vec<QuizItem>, Vec<AssetError>) {
+  let mut items = Vec::new();
+  let mut warnings = Vec::new();
+  for name in assets::list(assets::DATA_DIR).iter().filter(|name| import::is_aiken(name)) {
+    let (file_items, warning) = match assets::read_to_string(name) {
+      Ok(text) => aiken_file(name, &text),
+      Err(e) => (Vec::new(), Some(e)),
+    };
+    items.extend(file_items);
+    warnings.extend(warning);
+  }
+  (items, warnings)
+}
+
+// Un error de captura se reporta en lugar de perder la pregunta en silencio,
+// pero las demás preguntas del archivo se conservan
+fn aiken_file(name: &str, text: &str) -> (Vec<QuizItem>, Option<AssetError>) {
+  let unit = std::path::Path::new(name).file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+  let report = import::aiken(text, &unit);
+  let warning = (!report.skipped.is_empty()).then(|| {
+    let skipped: Vec<String> = report.skipped.iter().map(|skipped| format!("{}: {}", skipped.name, skipped.reason)).collect();
+    AssetError::Invalid {
+      path: assets::display_path(name),
+      message: format!("{} preguntas omitidas ({})", skipped.len(), skipped.join("; ")),
+    }
+  });
+  (report.items, warning)
+}
+
+pub fn load_bank_items(name: &str) -> Result<Vec<QuizItem>, AssetError> {
+  let items: Vec<QuizItem> = assets::load_json(name)?;
+  validate_bank(&items).map_err(|message| AssetError::Invalid { path: assets::display_path(name), message })?;
+  Ok(items)
+}
+
+// Mismo formato que el banco original: 4 espacios de sangría
+pub fn bank_to_json(items: &[QuizItem]) -> String {
+  let mut json = Vec::new();
+  let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+  let mut serializer = serde_json::Serializer::with_formatter(&mut json, formatter);
+  items.serialize(&mut serializer).expect("QuizItem siempre se puede serializar");
+  String::from_utf8(json).expect("serde_json escribe UTF-8")
+}
+
+// Lo que serde no revisa: que haya preguntas, que el tipo se conozca y que
+// la respuesta correcta (y las que tienen imagen) sean opciones
+pub fn validate_bank(items: &[QuizItem]) -> Result<(), String> {
+  if items.is_empty() {
+    return Err(String::from("el banco no tiene preguntas"));
+  }
+  for (idx, item) in items.iter().enumerate() {
+    if !ITEM_TYPES.contains(&item.tipo_reactivo.as_str()) {
+      return Err(format!("la pregunta {} tiene un tipo de reactivo desconocido: \"{}\"", idx + 1, item.tipo_reactivo));
+    }
+    if item.respuestas.len() < 2 {
+      return Err(format!("la pregunta {} necesita al menos dos respuestas", idx + 1));
+    }
+    if !item.respuestas.contains_key(&item.respuesta_correcta) {
+      return Err(format!(
+        "la pregunta {} tiene como respuesta correcta \"{}\", que no está entre sus respuestas",
+        idx + 1, item.respuesta_correcta
+      ));
+    }
+    if let Some(key) = item.imagenes_respuestas.keys().find(|key| !item.respuestas.contains_key(*key)) {
+      return Err(format!("la pregunta {} tiene una imagen para la respuesta \"{}\", que no existe", idx + 1, key));
+    }
+  }
+  Ok(())
+}
+
+enum CurrentScreen {
+  Menu,
+  PracticeSetup,
+  ExamSetup,
+  HotSeatSetup,
+  LanSetup,
+  LanHost,
+  LanStudent,
+  RaidSetup,
+  Raid,
+  DuelSetup,
+  Duel,
+  Editor,
+  Ingame,
+  Analisis,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct QuizItem {
+  #[serde(rename = "Unidad Temática")]
+  pub unidad_tematica: String,
+  
+  #[serde(rename = "Pregunta")]
+  pub pregunta: String,
+  
+  #[serde(rename = "Respuestas", serialize_with = "sorted_answers")]
+  pub respuestas: HashMap<String, String>,
+  
+  #[serde(rename = "Respuesta correcta")]
+  pub respuesta_correcta: String,
+  
+  #[serde(rename = "Tipo de reactivo")]
+  pub tipo_reactivo: String,
+
+  #[serde(rename = "Explicación", default, skip_serializing_if = "Option::is_none")]
+  pub explicacion: Option<String>,
+
+  #[serde(rename = "Dificultad", default, skip_serializing_if = "Option::is_none")]
+  pub dificultad: Option<String>,
+
+  // Rutas relativas a la carpeta del banco
+  #[serde(rename = "Imagen", default, skip_serializing_if = "Option::is_none")]
+  pub imagen: Option<String>,
+
+  // Imagen de cada opción, por clave
+  #[serde(rename = "Imágenes de respuestas", default, skip_serializing_if = "HashMap::is_empty", serialize_with = "sorted_answers")]
+  pub imagenes_respuestas: HashMap<String, String>,
+}
+
+pub const ITEM_TYPES: [&str; 3] = ["Opción Múltiple", "Verdadero o Falso", "Ejercicio"];
+
+// Las respuestas se escriben en orden de clave para que el JSON guardado no
+// cambie de orden cada vez
+fn sorted_answers<S: serde::Serializer>(respuestas: &HashMap<String, String>, serializer: S) -> Result<S::Ok, S::Error> {
+  respuestas.iter().collect::<std::collections::BTreeMap<_, _>>().serialize(serializer)
+}
+
+impl QuizItem {
+  pub fn time_limit(&self) -> Duration {
+    match self.tipo_reactivo.as_str() {
+      "Opción Múltiple" => Duration::from_secs(31),
+      "Verdadero o Falso" => Duration::from_secs(16),
+      "Ejercicio" => Duration::from_secs(300),
+      _ => Duration::from_secs(0)
+    }
+  }
+}
+
+impl Clone for QuizItem {
+  fn clone(&self) -> Self {
+    QuizItem {
+      unidad_tematica: self.unidad_tematica.clone(),
+      pregunta: self.pregunta.clone(),
+      respuestas: self.respuestas.clone(),
+      respuesta_correcta: self.respuesta_correcta.clone(),
+      tipo_reactivo: self.tipo_reactivo.clone(),
+      explicacion: self.explicacion.clone(),
+      dificultad: self.dificultad.clone(),
+      imagen: self.imagen.clone(),
+      imagenes_respuestas: self.imagenes_respuestas.clone(),
+    }
+  }
+}
+
+impl App {
+  pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    // Los avisos de los archivos Aiken se muestran en la pantalla de errores,
+    // que deja continuar porque el banco sí cargó
+    let (bank, asset_errors) = match load_quiz_items() {
+      Ok(loaded) => loaded,
+      Err(e) => (Vec::new(), vec![e]),
+    };
+    Self {
+      screen: CurrentScreen::Menu,
+      mode: GameMode::Normal,
+      quiz: Quiz::from_items(bank.clone()),
+      players: vec![Player::new("Jugador")],
+      turn: 0,
+      rnd_animation: RndNumberAnimation::default(),
+      profiles: Profiles::load(),
+      player_name: String::from("Jugador"),
+      hot_seat: HotSeatSettings::default(),
+      practice: PracticeSettings::default(),
+      exam_settings: ExamSettings::default(),
+      exam: ExamState::default(),
+      feedback: None,
+      answer_log: Vec::new(),
+      bank,
+      lan: LanSettings::default(),
+      classroom: Classroom::Inactive,
+      raid_settings: RaidSettings::default(),
+      raid: Raid::Inactive,
+      raid_playing: false,
+      duel_settings: DuelSettings::default(),
+      duel: Duel::Inactive,
+      editor: BankEditor::default(),
+      asset_errors,
+      bank_watcher: assets::FileWatcher::new(assets::DATA_DIR),
+      notice: None,
+    }
+  }
+
+  pub fn start_run(&mut self, mode: GameMode) {
+    let (quiz, rnd_animation) = match mode {
+      GameMode::Normal => (Quiz::from_items(self.bank.clone()), RndNumberAnimation::default()),
+      GameMode::RetoDiario(date) => (Quiz::from_seed(self.bank.clone(), date.seed()), RndNumberAnimation::from_seed(date.seed())),
+      GameMode::Infinito | GameMode::HotSeat => (Quiz::from_items(self.bank.clone()), RndNumberAnimation::default()),
+      GameMode::Practica => {
+        let items = self.practice.selected_items(&self.bank);
+        (Quiz::from_items(items), RndNumberAnimation::default())
+      }
+      GameMode::Examen => {
+        let items = self.exam_settings.draw(&self.bank, &mut rand::thread_rng());
+        // Si el banco se recargó sin las unidades elegidas no hay qué sortear
+        let Some(first) = items.first().cloned() else {
+          self.notice = Some(Notice::new("Las unidades elegidas ya no tienen preguntas; vuelve a armar el examen", true));
+          self.exam_settings = ExamSettings::from_bank(&self.bank);
+          self.screen = CurrentScreen::ExamSetup;
+          return;
+        };
+        let mut quiz = Quiz::from_items(items);
+        // El examen sigue el orden sorteado y usa un solo temporizador global
+        quiz.current_quiz = first;
+        quiz.duration = self.exam_settings.duration();
+        (quiz, RndNumberAnimation::default())
+      }
+    };
+    self.reset_run(mode, quiz, rnd_animation);
+  }
+
+  // Vuelve a leer el banco cuando cambia el archivo. Si el nuevo tiene
+  // errores se conserva el anterior; en práctica y examen las preguntas ya
+  // sorteadas no cambian hasta la siguiente partida.
+  pub fn reload_bank(&mut self) {
+    match load_quiz_items() {
+      Ok((bank, warnings)) => {
+        self.asset_errors.retain(|error| !error.path().contains(assets::DATA_DIR));
+        // A media partida los avisos van en la notificación, no en la pantalla de errores
+        self.notice = Some(match warnings.as_slice() {
+          [] => Notice::new(format!("Banco recargado: {} preguntas", bank.len()), false),
+          warnings => {
+            let warnings: Vec<String> = warnings.iter().map(AssetError::to_string).collect();
+            Notice::new(format!("Banco recargado: {} preguntas. {}", bank.len(), warnings.join(". ")), true)
+          }
+        });
+        assets::clear_image_cache();
+        self.set_bank(bank);
+      }
+      Err(e) => {
+        self.notice = Some(Notice::new(format!("No se recargó el banco. {}", e), true));
+      }
+    }
+  }
+
+  pub fn set_bank(&mut self, bank: Vec<QuizItem>) {
+    if self.raid_playing || !matches!(self.mode, GameMode::Practica | GameMode::Examen) {
+      self.quiz.replace_items(bank.clone());
+    }
+    // Las unidades de práctica y examen se vuelven a armar al entrar
+    if !matches!(self.screen, CurrentScreen::PracticeSetup | CurrentScreen::ExamSetup) {
+      self.practice.units.clear();
+      self.exam_settings.units.clear();
+    }
+    self.bank = bank;
+  }
+
+  pub fn start_practice_retry(&mut self) {
+    let missed = std::mem::take(&mut self.practice.missed);
+    self.reset_run(GameMode::Practica, Quiz::from_items(missed), RndNumberAnimation::default());
+  }
+
+  fn reset_run(&mut self, mode: GameMode, quiz: Quiz, rnd_animation: RndNumberAnimation) {
+    if let GameMode::RetoDiario(date) = mode {
+      // El intento se registra al empezar para que no se pueda repetir cerrando el juego
+      let profile = self.profiles.get_or_create(&self.player_name);
+      profile.daily_results.push(DailyResult {
+        date: date.to_string(),
+        completed: false,
+        won: false,
+        correct_answers: 0,
+        wrong_answers: 0,
+        best_hit: 0,
+      });
+      let _ = self.profiles.save();
+    }
+
+    self.mode = mode;
+    self.quiz = quiz;
+    self.rnd_animation = rnd_animation;
+    self.players = match mode {
+      GameMode::HotSeat => self.hot_seat.names[..self.hot_seat.num_players]
+        .iter()
+        .map(|name| Player::new(name))
+        .collect(),
+      _ => vec![Player::new(self.player_name.trim())],
+    };
+    self.turn = 0;
+    self.feedback = None;
+    self.answer_log.clear();
+    if mode == GameMode::Practica {
+      self.practice.missed.clear();
+    }
+    if mode == GameMode::Examen {
+      self.exam = ExamState::default();
+    }
+    self.screen = CurrentScreen::Ingame;
+  }
+
+  pub fn finish_run(&mut self) {
+    if let GameMode::RetoDiario(date) = self.mode {
+      let player = &self.players[0];
+      let won = player.is_alive();
+      let session_data = &player.session_data;
+      let profile = self.profiles.get_or_create(&self.player_name);
+      if let Some(result) = profile.daily_result_mut(&date.to_string()) {
+        result.completed = true;
+        result.won = won;
+        result.correct_answers = session_data.correct_answers;
+        result.wrong_answers = session_data.wrong_answers;
+        result.best_hit = session_data.best_hit;
+      }
+      let _ = self.profiles.save();
+    }
+    self.screen = CurrentScreen::Analisis;
+  }
+
+  // Anota la respuesta del jugador en turno a la pregunta actual con la vida
+  // que quedó; el daño de un acierto se completa al terminar el dado
+  pub fn log_answer(&mut self, key: Option<&str>) {
+    let item = &self.quiz.current_quiz;
+    let mut record = self.answer_record(item, key, self.quiz.asked_at.elapsed());
+    if self.mode.has_health() {
+      record.damage = Some(0);
+    }
+    self.profiles.record_answer(&record.player, &record.question, record.correct);
+    self.answer_log.push(record);
+  }
+
+  pub fn log_damage(&mut self, damage: u32) {
+    let (hero_health, enemy_health) = self.health_snapshot();
+    if let Some(record) = self.answer_log.last_mut() {
+      record.damage = Some(damage);
+      record.hero_health = hero_health;
+      record.enemy_health = enemy_health;
+    }
+  }
+
+  pub fn answer_record(&self, item: &QuizItem, key: Option<&str>, time: Duration) -> AnswerRecord {
+    let mut record = AnswerRecord::new(self.answer_log.len() + 1, &self.player().name, item, key, time.as_millis() as u64);
+    (record.hero_health, record.enemy_health) = self.health_snapshot();
+    record
+  }
+
+  // Vida en porcentaje; en el versus no hay enemigo
+  fn health_snapshot(&self) -> (Option<u8>, Option<u8>) {
+    if !self.mode.has_health() {
+      return (None, None);
+    }
+    let percent = |health: f32| Some((health * 100.0).round() as u8);
+    let health = &self.player().health;
+    let versus = self.mode == GameMode::HotSeat && self.hot_seat.versus;
+    (percent(health.hero_health), if versus { None } else { percent(health.enemy_health) })
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn export_results(&mut self) {
+    self.notice = Some(match crate::results::save(&self.player_name, self.mode.label(), &self.answer_log) {
+      Ok(base) => Notice::new(format!("Resultados guardados en {}.csv y .json", base.display()), false),
+      Err(e) => Notice::new(e, true),
+    });
+  }
+
+  pub fn player(&self) -> &Player {
+    &self.players[self.turn]
+  }
+
+  pub fn player_mut(&mut self) -> &mut Player {
+    &mut self.players[self.turn]
+  }
+
+  // En el modo versus el objetivo es el siguiente jugador vivo
+  pub fn target_idx(&self) -> Option<usize> {
+    if !(self.mode == GameMode::HotSeat && self.hot_seat.versus) {
+      return None;
+    }
+    (1..self.players.len())
+      .map(|offset| (self.turn + offset) % self.players.len())
+      .find(|&idx| self.players[idx].is_alive())
+  }
+
+  pub fn damage_target(&mut self, damage: f32) {
+    match self.target_idx() {
+      Some(idx) => {
+        let target = &mut self.players[idx].health;
+        target.hero_health = (target.hero_health - damage).clamp(0.0, 1.0);
+      }
+      // El enemigo es compartido: todos los jugadores llevan la misma copia de su vida
+      None => {
+        for player in self.players.iter_mut() {
+          player.health.damage_enemy(damage);
+        }
+      }
+    }
+  }
+
+  pub fn next_turn(&mut self) {
+    let len = self.players.len();
+    if let Some(next) = (1..=len)
+      .map(|offset| (self.turn + offset) % len)
+      .find(|&idx| self.players[idx].is_alive())
+    {
+      self.turn = next;
+    }
+  }
+
+  fn is_run_over(&self) -> bool {
+    let alive = self.players.iter().filter(|player| player.is_alive()).count();
+    if self.mode == GameMode::HotSeat && self.hot_seat.versus {
+      alive <= 1
+    } else {
+      alive == 0
+    }
+  }
+}
+
+impl eframe::App for App {
+  fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    if self.bank_watcher.is_active() {
+      if self.bank_watcher.poll() {
+        self.reload_bank();
+      }
+      ctx.request_repaint_after(Duration::from_secs(1));
+    }
+    notice_ui(self, ctx);
+
+    if !self.asset_errors.is_empty() {
+      asset_error_ui(self, ctx);
+      return;
+    }
+    match self.screen {
+      CurrentScreen::Menu => menu_ui(self, ctx),
+      CurrentScreen::PracticeSetup => practice_setup_ui(self, ctx),
+      CurrentScreen::ExamSetup => exam_setup_ui(self, ctx),
+      CurrentScreen::HotSeatSetup => hot_seat_setup_ui(self, ctx),
+      CurrentScreen::LanSetup => lan_setup_ui(self, ctx),
+      CurrentScreen::LanHost => lan_host_ui(self, ctx),
+      CurrentScreen::LanStudent => lan_student_ui(self, ctx),
+      CurrentScreen::RaidSetup => raid_setup_ui(self, ctx),
+      CurrentScreen::Raid => raid_ui(self, ctx),
+      CurrentScreen::DuelSetup => duel_setup_ui(self, ctx),
+      CurrentScreen::Duel => duel_ui(self, ctx),
+      CurrentScreen::Editor => editor_ui(self, ctx),
+      CurrentScreen::Ingame => ingame_ui(self, ctx),
+      CurrentScreen::Analisis => analisis_ui(self, ctx),
+    }
+    if !self.rnd_animation.is_animating {
+      ctx.request_repaint_after(Duration::from_millis(250));
+    }
+  }
+}
+
+fn notice_ui(app: &mut App, ctx: &egui::Context) {
+  let Some(notice) = &app.notice else { return };
+  if notice.shown_at.elapsed() >= NOTICE_DURATION {
+    app.notice = None;
+    return;
+  }
+  let color = if notice.is_error { Color32::LIGHT_RED } else { Color32::LIGHT_GREEN };
+  egui::Area::new(egui::Id::new("notice"))
+    .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -20.0))
+    .order(egui::Order::Foreground)
+    .show(ctx, |ui| {
+      egui::Frame::popup(ui.style()).show(ui, |ui| {
+        ui.label(RichText::new(&notice.text).size(15.0).color(color));
+      });
+    });
+  ctx.request_repaint_after(NOTICE_DURATION.saturating_sub(notice.shown_at.elapsed()));
+}
+
+// Se muestra antes del menú si algún archivo no se pudo cargar. Con las
+// fuentes se puede seguir (se usan las de egui); sin preguntas, no.
+fn asset_error_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(60.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("No se pudieron cargar algunos archivos").size(30.0).color(WHITE));
+      ui.add_space(20.0);
+      for error in &app.asset_errors {
+        ui.label(RichText::new(error.path()).size(15.0).color(Color32::YELLOW));
+        ui.label(RichText::new(error.to_string()).size(15.0));
+        ui.add_space(10.0);
+      }
+      #[cfg(not(target_arch = "wasm32"))]
+      if let Some(dir) = assets::override_dir() {
+        ui.label(RichText::new(format!("Carpeta de archivos: {}", dir.display())).size(13.0));
+      }
+      ui.add_space(20.0);
+
+      if !app.bank.is_empty() {
+        if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Continuar")).clicked() {
+          app.asset_errors.clear();
+        }
+        ui.add_space(10.0);
+      } else {
+        ui.label(RichText::new("Corrige el banco de preguntas y vuelve a abrir el juego").size(13.0));
+        ui.add_space(10.0);
+      }
+      #[cfg(not(target_arch = "wasm32"))]
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Salir")).clicked() {
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+      }
+    });
+  });
+}
+
+fn menu_ui(app: &mut App, ctx: &egui::Context) {
+  SidePanel::left("left_panel_menu")
+    .min_width(600.0)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+    ui.vertical_centered(|ui| {
+      ui.add(
+        Image::new(assets::image(assets::LOGO))
+        .max_width(200.0)
+        .max_height(200.0)
+      );
+      ui.label(RichText::new("CALABOZOS Y PREGUNTONES")
+        .family(egui::FontFamily::Name("CustomFont_1".into()))
+        .size(60.0)
+        .color(WHITE)
+      );
+      ui.add_space(40.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Editor de preguntas")).clicked() {
+        // Los cambios sin guardar se conservan al salir y volver a entrar
+        // Solo se edita questions.json; los archivos Aiken se editan aparte
+        if !app.editor.dirty {
+          app.editor = BankEditor::new(&load_bank_items(assets::QUESTIONS).unwrap_or_default());
+        }
+        app.screen = CurrentScreen::Editor;
+      }
+    })
+  });
+  
+  CentralPanel::default().show(ctx, |ui| {
+    let today = DailyDate::today();
+    let today_result = app.profiles
+      .get(&app.player_name)
+      .and_then(|profile| profile.daily_result(&today.to_string()))
+      .cloned();
+
+    ui.add_space(60.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Jugador").size(15.0));
+      ui.add_sized(egui::vec2(200.0, 25.0), egui::TextEdit::singleline(&mut app.player_name));
+      ui.add_space(20.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Iniciar")).clicked() {
+        app.start_run(GameMode::Normal);
+      }
+      ui.add_space(10.0);
+
+      let can_play_daily = today_result.is_none() && !app.player_name.trim().is_empty();
+      let daily_clicked = ui.add_enabled_ui(can_play_daily, |ui| {
+        ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Reto diario")).clicked()
+      }).inner;
+      if daily_clicked {
+        app.start_run(GameMode::RetoDiario(today));
+      }
+      match today_result {
+        Some(result) if result.completed => {
+          let outcome = if result.won { "Ganaste" } else { "Perdiste" };
+          ui.label(RichText::new(format!(
+            "Reto del {}: {} ({} aciertos, {} errores)",
+            today, outcome, result.correct_answers, result.wrong_answers
+          )).size(13.0));
+        }
+        Some(_) => {
+          ui.label(RichText::new(format!("Reto del {}: abandonado", today)).size(13.0));
+        }
+        None => {
+          ui.label(RichText::new(format!("Reto del {}: disponible", today)).size(13.0));
+        }
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Modo infinito")).clicked() {
+        app.start_run(GameMode::Infinito);
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Modo práctica")).clicked() {
+        if app.practice.units.is_empty() {
+          app.practice = PracticeSettings::from_bank(&app.bank);
+        }
+        app.screen = CurrentScreen::PracticeSetup;
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Simulacro de examen")).clicked() {
+        if app.exam_settings.units.is_empty() {
+          app.exam_settings = ExamSettings::from_bank(&app.bank);
+        }
+        app.screen = CurrentScreen::ExamSetup;
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Multijugador local")).clicked() {
+        app.screen = CurrentScreen::HotSeatSetup;
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Clase en red")).clicked() {
+        app.lan.error = None;
+        app.screen = CurrentScreen::LanSetup;
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Raid cooperativa")).clicked() {
+        app.raid_settings.error = None;
+        app.screen = CurrentScreen::RaidSetup;
+      }
+      ui.add_space(10.0);
+
+      if ui.add_sized(egui::vec2(200.0, 40.0), egui::Button::new("Duelo 1 vs 1")).clicked() {
+        app.duel_settings.error = None;
+        app.screen = CurrentScreen::DuelSetup;
+      }
+    });
+  });
+}
+
+fn editor_ui(app: &mut App, ctx: &egui::Context) {
+  TopBottomPanel::top("top_panel_editor")
+    .resizable(false)
+    .show(ctx, |ui| {
+      ui.add_space(5.0);
+      ui.horizontal(|ui| {
+        if ui.button("Volver al menú").clicked() {
+          app.screen = CurrentScreen::Menu;
+        }
+        ui.add_space(10.0);
+        ui.label(RichText::new("Editor de preguntas").size(20.0).color(WHITE));
+        ui.add_space(20.0);
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+          ui.label("Guardar en");
+          ui.add_sized(egui::vec2(450.0, 20.0), egui::TextEdit::singleline(&mut app.editor.path));
+        }
+        let label = if app.editor.dirty { "Guardar *" } else { "Guardar" };
+        if ui.button(label).clicked() {
+          save_editor(app);
+        }
+      });
+      #[cfg(not(target_arch = "wasm32"))]
+      ui.horizontal(|ui| {
+        ui.label("Importar o exportar (.xml, .gift, .csv)");
+        ui.add_sized(egui::vec2(450.0, 20.0), egui::TextEdit::singleline(&mut app.editor.file_path));
+        if ui.button("Importar").clicked() {
+          let result = app.editor.import();
+          app.notice = Some(match result {
+            Ok(()) => Notice::new(app.editor.import_report.as_ref().map_or_else(String::new, |(summary, _)| summary.clone()), false),
+            Err(e) => Notice::new(e, true),
+          });
+        }
+        if ui.button("Exportar CSV").clicked() {
+          app.notice = Some(match app.editor.export_csv() {
+            Ok(()) => Notice::new(format!("Banco exportado a {}", app.editor.file_path.trim()), false),
+            Err(e) => Notice::new(e, true),
+          });
+        }
+      });
+      ui.add_space(5.0);
+  });
+
+  SidePanel::left("left_panel_editor")
+    .exact_width(330.0)
+    .resizable(false)
+    .show(ctx, |ui| {
+      ui.add_space(5.0);
+      ui.horizontal(|ui| {
+        ui.label("Buscar");
+        ui.text_edit_singleline(&mut app.editor.search);
+      });
+      ui.horizontal(|ui| {
+        if ui.button("Nueva").clicked() {
+          app.editor.add_item();
+        }
+        if ui.add_enabled(app.editor.selected.is_some(), egui::Button::new("Eliminar")).clicked() {
+          app.editor.remove_selected();
+        }
+        ui.label(format!("{} preguntas", app.editor.items.len()));
+      });
+      if let Some((summary, skipped)) = &app.editor.import_report {
+        egui::CollapsingHeader::new(format!("Importación: {}", summary)).show(ui, |ui| {
+          for item in skipped {
+            ui.label(RichText::new(format!("{}: {}", item.name, item.reason)).size(12.0));
+          }
+        });
+      }
+      ui.separator();
+
+      let matches = app.editor.matches();
+      egui::ScrollArea::vertical().show(ui, |ui| {
+        for idx in matches {
+          let item = &app.editor.items[idx];
+          let mut text: String = item.pregunta.chars().take(40).collect();
+          if text.is_empty() {
+            text = String::from("(sin texto)");
+          }
+          let selected = app.editor.selected == Some(idx);
+          if ui.selectable_label(selected, format!("{}. {}", idx + 1, text)).clicked() {
+            app.editor.selected = Some(idx);
+          }
+        }
+      });
+  });
+
+  SidePanel::right("right_panel_editor")
+    .exact_width(400.0)
+    .resizable(false)
+    .show(ctx, |ui| {
+      ui.add_space(5.0);
+      ui.label(RichText::new("Vista previa").size(15.0).color(Color32::GOLD));
+      if let Some(item) = app.editor.selected_item() {
+        egui::ScrollArea::vertical().show(ui, |ui| components::question_preview(ui, item));
+      }
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    let units = app.editor.units();
+    let editor = &mut app.editor;
+    let Some(idx) = editor.selected.filter(|&idx| idx < editor.items.len()) else {
+      ui.label(RichText::new("Selecciona una pregunta o crea una nueva").size(15.0));
+      return;
+    };
+    let mut changed = false;
+    let mut new_type = None;
+    let mut removed_key = None;
+    let mut add_answer = false;
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+      let item = &mut editor.items[idx];
+      ui.label(RichText::new(format!("Pregunta {}", idx + 1)).size(20.0).color(WHITE));
+      ui.add_space(10.0);
+
+      ui.label("Unidad temática");
+      ui.horizontal(|ui| {
+        changed |= ui.add_sized(egui::vec2(330.0, 20.0), egui::TextEdit::singleline(&mut item.unidad_tematica)).changed();
+        ui.menu_button("Elegir", |ui| {
+          for unit in &units {
+            if ui.button(unit).clicked() {
+              item.unidad_tematica = unit.clone();
+              changed = true;
+              ui.close_menu();
+            }
+          }
+        });
+      });
+      ui.add_space(10.0);
+
+      ui.label("Tipo de reactivo");
+      ui.horizontal(|ui| {
+        for tipo in ITEM_TYPES {
+          if ui.radio(item.tipo_reactivo == tipo, tipo).clicked() {
+            new_type = Some(tipo);
+          }
+        }
+      });
+      ui.add_space(10.0);
+
+      ui.label("Pregunta (admite **negritas**, *cursivas*, listas con \"- \" y fórmulas entre $, p. ej. $X_{n+1}$)");
+      changed |= ui.add(
+        egui::TextEdit::multiline(&mut item.pregunta)
+          .desired_width(f32::INFINITY)
+          .desired_rows(3)
+      ).changed();
+      ui.add_space(10.0);
+
+      ui.label("Imagen (opcional, ruta relativa a la carpeta del banco)");
+      let mut imagen = item.imagen.clone().unwrap_or_default();
+      if ui.add_sized(egui::vec2(330.0, 20.0), egui::TextEdit::singleline(&mut imagen)).changed() {
+        item.imagen = (!imagen.trim().is_empty()).then_some(imagen);
+        changed = true;
+      }
+      ui.add_space(10.0);
+
+      ui.label("Respuestas (marca la correcta)");
+      let mut keys: Vec<String> = item.respuestas.keys().cloned().collect();
+      keys.sort();
+      for key in keys {
+        ui.horizontal(|ui| {
+          if ui.radio(item.respuesta_correcta == key, &key).clicked() {
+            item.respuesta_correcta = key.clone();
+            changed = true;
+          }
+          if let Some(answer) = item.respuestas.get_mut(&key) {
+            changed |= ui.add_sized(egui::vec2(230.0, 20.0), egui::TextEdit::singleline(answer)).changed();
+          }
+          let mut imagen = item.imagenes_respuestas.get(&key).cloned().unwrap_or_default();
+          if ui.add_sized(egui::vec2(120.0, 20.0), egui::TextEdit::singleline(&mut imagen).hint_text("imagen")).changed() {
+            if imagen.trim().is_empty() {
+              item.imagenes_respuestas.remove(&key);
+            } else {
+              item.imagenes_respuestas.insert(key.clone(), imagen);
+            }
+            changed = true;
+          }
+          if ui.button("Quitar").clicked() {
+            removed_key = Some(key.clone());
+          }
+        });
+      }
+      ui.horizontal(|ui| {
+        ui.label("Clave");
+        ui.add_sized(egui::vec2(40.0, 20.0), egui::TextEdit::singleline(&mut editor.new_key));
+        add_answer = ui.button("Agregar respuesta").clicked();
+      });
+      ui.add_space(10.0);
+
+      ui.label("Dificultad (opcional)");
+      let mut dificultad = item.dificultad.clone().unwrap_or_default();
+      if ui.add_sized(egui::vec2(200.0, 20.0), egui::TextEdit::singleline(&mut dificultad)).changed() {
+        item.dificultad = (!dificultad.trim().is_empty()).then_some(dificultad);
+        changed = true;
+      }
+      ui.add_space(10.0);
+
+      ui.label("Explicación (opcional)");
+      let mut explicacion = item.explicacion.clone().unwrap_or_default();
+      if ui.add(
+        egui::TextEdit::multiline(&mut explicacion)
+          .desired_width(f32::INFINITY)
+          .desired_rows(2)
+      ).changed() {
+        item.explicacion = (!explicacion.trim().is_empty()).then_some(explicacion);
+        changed = true;
+      }
+    });
+
+    if let Some(tipo) = new_type {
+      editor.set_type(tipo);
+    }
+    if let Some(key) = removed_key {
+      editor.remove_answer(&key);
+    }
+    if add_answer {
+      editor.add_answer();
+    }
+    editor.dirty |= changed;
+  });
+}
+
+fn save_editor(app: &mut App) {
+  match app.editor.save() {
+    Ok(()) => {
+      let mut bank = app.editor.items.clone();
+      // Los avisos de los Aiken ya se mostraron al cargar el banco
+      bank.extend(load_aiken_items().0);
+      app.set_bank(bank);
+      #[cfg(not(target_arch = "wasm32"))]
+      let text = format!("Banco guardado en {}", app.editor.path);
+      #[cfg(target_arch = "wasm32")]
+      let text = String::from("Banco actualizado para esta sesión");
+      app.notice = Some(Notice::new(text, false));
+    }
+    Err(e) => app.notice = Some(Notice::new(format!("No se guardó: {}", e), true)),
+  }
+}
+
+fn practice_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Modo práctica").size(40.0));
+      ui.label(RichText::new("Sin vida ni tiempo: elige qué quieres repasar").size(15.0));
+    });
+    ui.add_space(40.0);
+
+    ui.columns(2, |columns| {
+      columns[0].label(RichText::new("Unidades temáticas").size(20.0).color(WHITE));
+      columns[0].add_space(10.0);
+      for (unit, selected) in app.practice.units.iter_mut() {
+        columns[0].checkbox(selected, RichText::new(unit.as_str()).size(15.0));
+      }
+
+      columns[1].label(RichText::new("Tipos de reactivo").size(20.0).color(WHITE));
+      columns[1].add_space(10.0);
+      for (tipo, selected) in app.practice.types.iter_mut() {
+        columns[1].checkbox(selected, RichText::new(tipo.as_str()).size(15.0));
+      }
+    });
+
+    let available = app.practice.selected_items(&app.bank).len();
+    ui.add_space(40.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new(format!("{} preguntas seleccionadas", available)).size(15.0));
+      ui.add_space(10.0);
+      let start_clicked = ui.add_enabled_ui(available > 0, |ui| {
+        ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Comenzar").size(15.0))).clicked()
+      }).inner;
+      if start_clicked {
+        app.start_run(GameMode::Practica);
+      }
+      ui.add_space(10.0);
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn exam_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Simulacro de examen").size(40.0));
+      ui.label(RichText::new("Preguntas por unidad temática y tipo de reactivo").size(15.0));
+    });
+    ui.add_space(30.0);
+
+    let settings = &mut app.exam_settings;
+    egui::Grid::new("exam_distribution")
+      .spacing([30.0, 12.0])
+      .show(ui, |ui| {
+        ui.label("");
+        for tipo in &settings.types {
+          ui.label(RichText::new(tipo.as_str()).size(15.0).color(WHITE));
+        }
+        ui.end_row();
+
+        for (unit_idx, unit) in settings.units.iter().enumerate() {
+          ui.label(RichText::new(unit.as_str()).size(15.0));
+          for type_idx in 0..settings.types.len() {
+            let max = settings.available[unit_idx][type_idx];
+            ui.add(
+              egui::DragValue::new(&mut settings.distribution[unit_idx][type_idx])
+                .range(0..=max)
+                .suffix(format!(" / {}", max))
+            );
+          }
+          ui.end_row();
+        }
+      });
+
+    ui.add_space(20.0);
+    ui.horizontal(|ui| {
+      ui.label(RichText::new("Duración del examen").size(15.0));
+      ui.add(egui::DragValue::new(&mut settings.minutes).range(1..=180).suffix(" min"));
+    });
+
+    let total = settings.total_questions();
+    ui.add_space(30.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new(format!("{} preguntas en total", total)).size(15.0));
+      ui.add_space(10.0);
+      let start_clicked = ui.add_enabled_ui(total > 0, |ui| {
+        ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Comenzar examen").size(15.0))).clicked()
+      }).inner;
+      if start_clicked {
+        app.start_run(GameMode::Examen);
+      }
+      ui.add_space(10.0);
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn hot_seat_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Multijugador local").size(40.0));
+      ui.label(RichText::new("Los jugadores se turnan para contestar en esta misma computadora").size(15.0));
+    });
+    ui.add_space(40.0);
+
+    let settings = &mut app.hot_seat;
+    ui.vertical_centered(|ui| {
+      ui.horizontal(|ui| {
+        ui.add_space(420.0);
+        ui.label(RichText::new("Jugadores").size(15.0));
+        ui.add(egui::Slider::new(&mut settings.num_players, 2..=4));
+      });
+      ui.add_space(15.0);
+      for name in settings.names[..settings.num_players].iter_mut() {
+        ui.add_sized(egui::vec2(250.0, 25.0), egui::TextEdit::singleline(name));
+        ui.add_space(5.0);
+      }
+      ui.add_space(15.0);
+      ui.horizontal(|ui| {
+        ui.add_space(380.0);
+        ui.radio_value(&mut settings.versus, false, RichText::new("Contra un enemigo compartido").size(15.0));
+        ui.radio_value(&mut settings.versus, true, RichText::new("Todos contra todos").size(15.0));
+      });
+    });
+
+    let names_ok = settings.names[..settings.num_players].iter().all(|name| !name.trim().is_empty());
+    ui.add_space(40.0);
+    ui.vertical_centered(|ui| {
+      let start_clicked = ui.add_enabled_ui(names_ok, |ui| {
+        ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Comenzar").size(15.0))).clicked()
+      }).inner;
+      if start_clicked {
+        app.start_run(GameMode::HotSeat);
+      }
+      ui.add_space(10.0);
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn lan_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Clase en red").size(40.0));
+      ui.label(RichText::new("El profesor abre una sala y los alumnos se unen con su dirección").size(15.0));
+    });
+    ui.add_space(60.0);
+
+    ui.columns(2, |columns| {
+      columns[0].vertical_centered(|ui| {
+        ui.label(RichText::new("Profesor").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.horizontal(|ui| {
+          ui.add_space(150.0);
+          ui.label(RichText::new("Puerto").size(15.0));
+          ui.add(egui::DragValue::new(&mut app.lan.port).range(1024..=65535));
+        });
+        ui.add_space(15.0);
+        if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Abrir sala").size(15.0))).clicked() {
+          match ClassroomHost::start(app.lan.port, app.bank.clone()) {
+            Ok(host) => {
+              app.classroom = Classroom::Host(host);
+              app.screen = CurrentScreen::LanHost;
+            }
+            Err(e) => app.lan.error = Some(format!("No se pudo abrir la sala: {}", e)),
+          }
+        }
+      });
+
+      columns[1].vertical_centered(|ui| {
+        ui.label(RichText::new("Alumno").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.label(RichText::new(format!("Nombre: {}", app.player_name)).size(15.0));
+        ui.add_sized(egui::vec2(250.0, 25.0), egui::TextEdit::singleline(&mut app.lan.address));
+        ui.add_space(15.0);
+        let can_join = !app.player_name.trim().is_empty();
+        let join_clicked = ui.add_enabled_ui(can_join, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Unirse").size(15.0))).clicked()
+        }).inner;
+        if join_clicked {
+          match ClassroomStudent::join(app.lan.address.trim(), app.player_name.trim()) {
+            Ok(student) => {
+              app.classroom = Classroom::Student(student);
+              app.screen = CurrentScreen::LanStudent;
+            }
+            Err(e) => app.lan.error = Some(format!("No se pudo conectar a {}: {}", app.lan.address, e)),
+          }
+        }
+      });
+    });
+
+    ui.add_space(30.0);
+    ui.vertical_centered(|ui| {
+      if let Some(error) = &app.lan.error {
+        ui.label(RichText::new(error).size(15.0).color(Color32::RED));
+        ui.add_space(15.0);
+      }
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn lan_host_ui(app: &mut App, ctx: &egui::Context) {
+  let Classroom::Host(host) = &mut app.classroom else {
+    app.screen = CurrentScreen::LanSetup;
+    return;
+  };
+  host.update();
+  let mut close_session = false;
+
+  TopBottomPanel::top("top_panel_lan_host")
+    .resizable(false)
+    .show(ctx, |ui| {
+      let port = host.local_addr().port();
+      let ip = host.lan_ip.map(|ip| ip.to_string()).unwrap_or_else(|| String::from("<ip del equipo>"));
+      ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("Sala abierta en {}:{}", ip, port)).size(18.0).color(WHITE));
+        ui.add_space(20.0);
+        ui.label(RichText::new(format!("(en esta misma máquina: 127.0.0.1:{})", port)).size(13.0));
+      });
+  });
+
+  SidePanel::right("right_panel_lan_host")
+    .min_width(420.0)
+    .resizable(false)
+    .show(ctx, |ui| {
+      components::student_dashboard(ui, host);
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(10.0);
+    ui.label(RichText::new(format!("{} alumnos conectados", host.connected_students())).size(15.0));
+    ui.add_space(10.0);
+
+    match &host.current {
+      Some(current) => {
+        let remaining = current.remaining().as_secs();
+        ui.vertical_centered(|ui| {
+          let (status, color) = if current.revealed {
+            ("Respuesta revelada", Color32::GOLD)
+          } else if current.is_paused() {
+            ("En pausa", Color32::ORANGE)
+          } else {
+            ("", WHITE)
+          };
+          ui.heading(RichText::new(format!("{:02}:{:02}", remaining / 60, remaining % 60)).size(50.0).color(WHITE));
+          ui.label(RichText::new(status).size(15.0).color(color));
+          ui.label(RichText::new(format!("Pregunta {}", current.id)).size(15.0));
+          ui.add_space(10.0);
+          components::quiz_block(ui, &current.item.pregunta, 20.0, Some(WHITE));
+          components::question_image(ui, &current.item);
+          ui.add_space(10.0);
+          ui.label(RichText::new(format!(
+            "{} de {} respondieron",
+            current.answers.len(),
+            host.connected_students()
+          )).size(15.0));
+        });
+        ui.add_space(10.0);
+        components::answer_distribution(ui, current);
+      }
+      None => {
+        ui.vertical_centered(|ui| {
+          ui.label(RichText::new("Cuando todos estén conectados, envía la primera pregunta").size(18.0));
+        });
+      }
+    }
+
+    ui.add_space(20.0);
+    ui.horizontal(|ui| {
+      let button_size = egui::vec2(170.0, 40.0);
+      let has_question = host.current.is_some();
+      let revealed = host.current.as_ref().is_some_and(|current| current.revealed);
+      let paused = host.current.as_ref().is_some_and(|current| current.is_paused());
+
+      let label = if has_question { "Siguiente pregunta" } else { "Enviar pregunta" };
+      if ui.add_sized(button_size, egui::Button::new(RichText::new(label).size(15.0))).clicked() {
+        host.next_question();
+      }
+      ui.add_enabled_ui(has_question && !revealed, |ui| {
+        let label = if paused { "Reanudar" } else { "Pausar" };
+        if ui.add_sized(button_size, egui::Button::new(RichText::new(label).size(15.0))).clicked() {
+          host.toggle_pause();
+        }
+        if ui.add_sized(button_size, egui::Button::new(RichText::new("Revelar respuesta").size(15.0))).clicked() {
+          host.reveal();
+        }
+        if ui.add_sized(button_size, egui::Button::new(RichText::new("Saltar").size(15.0))).clicked() {
+          host.skip();
+        }
+      });
+    });
+    ui.add_space(10.0);
+    if ui.add_sized(egui::vec2(170.0, 40.0), egui::Button::new(RichText::new("Terminar sesión").size(15.0))).clicked() {
+      host.end();
+      close_session = true;
+    }
+  });
+
+  if close_session {
+    app.classroom = Classroom::Inactive;
+    app.screen = CurrentScreen::Menu;
+  }
+}
+
+fn lan_student_ui(app: &mut App, ctx: &egui::Context) {
+  let Classroom::Student(student) = &mut app.classroom else {
+    app.screen = CurrentScreen::LanSetup;
+    return;
+  };
+  student.update();
+  let mut leave = false;
+
+  TopBottomPanel::top("top_panel_lan_student")
+    .resizable(false)
+    .show(ctx, |ui| {
+      ui.horizontal(|ui| {
+        ui.label(RichText::new(&student.name).size(18.0).color(WHITE));
+        ui.add_space(20.0);
+        let (status, color) = if !student.connected {
+          ("Desconectado", Color32::RED)
+        } else if student.finished {
+          ("Sesión terminada", Color32::GOLD)
+        } else {
+          ("Conectado", Color32::GREEN)
+        };
+        ui.label(RichText::new(status).size(15.0).color(color));
+        ui.add_space(ui.available_width() - 60.0);
+        if ui.button("Salir").clicked() {
+          leave = true;
+        }
+      });
+  });
+
+  SidePanel::right("right_panel_lan_student")
+    .min_width(380.0)
+    .resizable(false)
+    .show(ctx, |ui| {
+      components::standings_table(ui, &student.standings);
+  });
+
+  TopBottomPanel::bottom("bottom_panel_lan_student")
+    .min_height(250.0)
+    .resizable(false)
+    .show(ctx, |ui| {
+      components::lan_answers(ui, student);
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    match &student.current {
+      Some(current) if !student.finished => {
+        let remaining = current.remaining().as_secs();
+        ui.vertical_centered(|ui| {
+          ui.heading(RichText::new(format!("{:02}:{:02}", remaining / 60, remaining % 60)).size(60.0).color(WHITE));
+          if current.is_paused() {
+            ui.label(RichText::new("El profesor pausó la pregunta").size(18.0).color(Color32::ORANGE));
+          }
+        });
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+          components::quiz_block(ui, &current.item.pregunta, 30.0, Some(WHITE));
+          components::question_image(ui, &current.item);
+        });
+      }
+      _ => {
+        ui.centered_and_justified(|ui| {
+          let message = if student.finished {
+            "El profesor terminó la sesión"
+          } else {
+            "Esperando la siguiente pregunta..."
+          };
+          ui.label(RichText::new(message).size(25.0));
+        });
+      }
+    }
+  });
+
+  if leave {
+    app.classroom = Classroom::Inactive;
+    app.screen = CurrentScreen::Menu;
+  }
+}
+
+fn raid_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Raid cooperativa").size(40.0));
+      ui.label(RichText::new("Todo el equipo contra un mismo jefe por red local").size(15.0));
+      ui.label(RichText::new(format!("Jugando como: {}", app.player_name)).size(15.0));
+    });
+    ui.add_space(50.0);
+
+    let can_play = !app.player_name.trim().is_empty();
+    ui.columns(2, |columns| {
+      columns[0].vertical_centered(|ui| {
+        ui.label(RichText::new("Crear raid").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.horizontal(|ui| {
+          ui.add_space(150.0);
+          ui.label(RichText::new("Puerto").size(15.0));
+          ui.add(egui::DragValue::new(&mut app.raid_settings.port).range(1024..=65535));
+        });
+        ui.checkbox(&mut app.raid_settings.shared_streak, RichText::new("Racha compartida como buff de equipo").size(15.0));
+        ui.add_space(15.0);
+        let create_clicked = ui.add_enabled_ui(can_play, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Crear").size(15.0))).clicked()
+        }).inner;
+        if create_clicked {
+          match RaidHost::start(app.raid_settings.port, app.player_name.trim(), app.raid_settings.shared_streak) {
+            Ok(host) => {
+              app.raid = Raid::Host(Box::new(host));
+              app.raid_playing = false;
+              app.screen = CurrentScreen::Raid;
+            }
+            Err(e) => app.raid_settings.error = Some(format!("No se pudo crear la raid: {}", e)),
+          }
+        }
+      });
+
+      columns[1].vertical_centered(|ui| {
+        ui.label(RichText::new("Unirse a una raid").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.add_sized(egui::vec2(250.0, 25.0), egui::TextEdit::singleline(&mut app.raid_settings.address));
+        ui.add_space(15.0);
+        let join_clicked = ui.add_enabled_ui(can_play, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Unirse").size(15.0))).clicked()
+        }).inner;
+        if join_clicked {
+          match RaidClient::join(app.raid_settings.address.trim(), app.player_name.trim()) {
+            Ok(client) => {
+              app.raid = Raid::Client(client);
+              app.raid_playing = false;
+              app.screen = CurrentScreen::Raid;
+            }
+            Err(e) => app.raid_settings.error = Some(format!("No se pudo conectar a {}: {}", app.raid_settings.address, e)),
+          }
+        }
+      });
+    });
+
+    ui.add_space(30.0);
+    ui.vertical_centered(|ui| {
+      if let Some(error) = &app.raid_settings.error {
+        ui.label(RichText::new(error).size(15.0).color(Color32::RED));
+        ui.add_space(15.0);
+      }
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn raid_ui(app: &mut App, ctx: &egui::Context) {
+  app.raid.update();
+  let snapshot = app.raid.snapshot().cloned();
+  let mut leave = false;
+
+  match snapshot {
+    None => {
+      CentralPanel::default().show(ctx, |ui| {
+        ui.centered_and_justified(|ui| {
+          let message = match app.raid.rejection() {
+            Some(reason) => reason,
+            None if app.raid.is_connected() => "Conectando con la raid...",
+            None => "No se pudo entrar a la raid",
+          };
+          ui.label(RichText::new(message).size(25.0));
+        });
+      });
+      TopBottomPanel::bottom("bottom_panel_raid_connecting").show(ctx, |ui| {
+        if ui.button("Salir").clicked() {
+          leave = true;
+        }
+      });
+    }
+    Some(snapshot) => match snapshot.status {
+      RaidStatus::Lobby => raid_lobby_ui(app, ctx, &snapshot, &mut leave),
+      RaidStatus::Running => raid_battle_ui(app, ctx, &snapshot),
+      RaidStatus::Victory | RaidStatus::Defeat => raid_results_ui(ctx, &snapshot, &mut leave),
+    },
+  }
+
+  if leave {
+    app.raid = Raid::Inactive;
+    // Las preguntas de la raid ya no siguen al banco al recargarlo
+    app.raid_playing = false;
+    app.screen = CurrentScreen::Menu;
+  }
+}
+
+fn raid_lobby_ui(app: &mut App, ctx: &egui::Context, snapshot: &RaidSnapshot, leave: &mut bool) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Sala de espera").size(40.0));
+      if let Raid::Host(host) = &app.raid {
+        let port = host.local_addr().port();
+        let ip = host.lan_ip.map(|ip| ip.to_string()).unwrap_or_else(|| String::from("127.0.0.1"));
+        ui.label(RichText::new(format!("Los demás se unen a {}:{}", ip, port)).size(15.0));
+      }
+      if !app.raid.is_connected() {
+        ui.label(RichText::new("Se perdió la conexión con el anfitrión").size(15.0).color(Color32::RED));
+      }
+      ui.add_space(30.0);
+      for player in &snapshot.players {
+        ui.label(RichText::new(&player.name).size(20.0).color(WHITE));
+      }
+      ui.add_space(30.0);
+
+      if let Raid::Host(host) = &mut app.raid {
+        if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Comenzar raid").size(15.0))).clicked() {
+          host.begin();
+        }
+      } else {
+        ui.label(RichText::new("Esperando a que el anfitrión comience...").size(18.0));
+      }
+      ui.add_space(10.0);
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Salir").size(15.0))).clicked() {
+        *leave = true;
+      }
+    });
+  });
+}
+
+fn raid_battle_ui(app: &mut App, ctx: &egui::Context, snapshot: &RaidSnapshot) {
+  // Cada jugador contesta sus propias preguntas; solo el resultado viaja por la red
+  if !app.raid_playing {
+    app.quiz = Quiz::from_items(app.bank.clone());
+    app.raid_playing = true;
+  }
+  let me = app.raid.local_player_id().and_then(|id| snapshot.player(id)).cloned();
+  let alive = me.as_ref().is_some_and(|player| player.is_alive()) && app.raid.is_connected();
+  let streak = app.raid.local_player_id().map(|id| snapshot.streak_for(id)).unwrap_or_default();
+
+  let remaining = app.quiz.duration.saturating_sub(app.quiz.start_time.elapsed());
+  if alive && remaining == Duration::ZERO {
+    app.raid.answer(false);
+    components::select_new_quiz(app);
+  }
+
+  TopBottomPanel::top("top_panel_raid")
+    .min_height(15.)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.horizontal(|ui| {
+        ui.label(RichText::new(&app.quiz.current_quiz.unidad_tematica).size(15.0));
+        if snapshot.shared_streak && streak != StreakState::NoStreak {
+          ui.add_space(20.0);
+          ui.label(RichText::new(format!("Buff de equipo x{}", streak.multiplier())).size(15.0).color(Color32::ORANGE));
+        }
+        if !app.raid.is_connected() {
+          ui.add_space(20.0);
+          ui.label(RichText::new("Desconectado").size(15.0).color(Color32::RED));
+        }
+      });
+      ui.separator();
+  });
+
+  TopBottomPanel::bottom("bottom_panel_raid")
+    .min_height(250.0)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.separator();
+      if alive {
+        components::raid_answers(ui, app);
+      } else {
+        ui.centered_and_justified(|ui| {
+          ui.label(RichText::new("Caíste en combate; tu equipo sigue luchando").size(20.0));
+        });
+      }
+  });
+
+  SidePanel::left("left_panel_raid")
+    .min_width(350.0)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.add_space(5.0);
+      ui.vertical_centered(|ui| {
+        if let Some(me) = &me {
+          ui.label(RichText::new(&me.name).size(18.0).color(Color32::GOLD));
+          components::health_bar(ui, me.health, false);
+        }
+        if streak == StreakState::NoStreak {
+          ui.add_space(100.0);
+        } else {
+          ui.add(
+            egui::Button::new(RichText::new(format!("Multiplicador x{}", streak.multiplier())).color(WHITE).size(15.0))
+              .fill(if streak == StreakState::X2 { Color32::ORANGE } else { Color32::RED })
+              .sense(egui::Sense::hover())
+          );
+          ui.add_space(79.0);
+        }
+        ui.add(
+          Image::new(assets::image(assets::HERO))
+          .max_width(180.0)
+          .max_height(180.0)
+        );
+      });
+  });
+
+  SidePanel::right("right_panel_raid")
+    .min_width(350.0)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.add_space(5.0);
+      ui.vertical_centered(|ui| {
+        ui.label(RichText::new("Jefe").size(18.0).color(Color32::RED));
+        components::health_bar(ui, snapshot.boss_health, true);
+        ui.add_space(10.0);
+        ui.add(
+          Image::new(assets::image(assets::ENEMY))
+          .max_width(150.0)
+          .max_height(150.0)
+        );
+        ui.add_space(10.0);
+        for player in &snapshot.players {
+          let color = if !player.connected { Color32::DARK_GRAY } else if player.is_alive() { WHITE } else { Color32::RED };
+          ui.label(RichText::new(&player.name).size(13.0).color(color));
+          components::health_bar(ui, player.health, false);
+        }
+      });
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    ui.vertical_centered(|ui| {
+      let secs = remaining.as_secs();
+      ui.heading(RichText::new(format!("{:02}:{:02}", secs / 60, secs % 60)).size(50.).color(WHITE));
+      ui.add_space(5.0);
+      for line in &snapshot.log {
+        ui.label(RichText::new(line).size(13.0));
+      }
+    });
+    ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+      components::quiz_block(ui, &app.quiz.current_quiz.pregunta, 25., Some(WHITE));
+      // De abajo hacia arriba: la imagen queda sobre el texto
+      components::question_image(ui, &app.quiz.current_quiz);
+    });
+  });
+}
+
+fn raid_results_ui(ctx: &egui::Context, snapshot: &RaidSnapshot, leave: &mut bool) {
+  SidePanel::left("left_raid_results_panel")
+    .resizable(false)
+    .exact_width(400.0)
+    .show(ctx, |ui| {
+      let title = if snapshot.status == RaidStatus::Victory { "VICTORIA" } else { "DERROTA" };
+      ui.centered_and_justified(|ui| {
+        ui.label(RichText::new(title).family(egui::FontFamily::Name("CustomFont_1".into())).size(50.0))
+      });
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Resultados de la raid").size(40.0))
+    });
+    ui.add_space(60.0);
+    egui::Grid::new("raid_results_table")
+      .spacing([30.0, 20.0])
+      .show(ui, |ui| {
+        for header in ["Jugador", "Aciertos", "Errores", "Daño infligido", "Vida restante"] {
+          ui.label(RichText::new(header).size(18.0).color(WHITE));
+        }
+        ui.end_row();
+        for player in &snapshot.players {
+          ui.label(RichText::new(&player.name).size(18.0));
+          ui.label(RichText::new(player.correct.to_string()).size(18.0));
+          ui.label(RichText::new(player.wrong.to_string()).size(18.0));
+          ui.label(RichText::new(format!("{} pts", player.damage_dealt)).size(18.0));
+          ui.label(RichText::new(format!("{}%", (player.health * 100.0) as u8)).size(18.0));
+          ui.end_row();
+        }
+      });
+    ui.add_space(50.0);
+    if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+      *leave = true;
+    }
+  });
+}
+
+fn duel_setup_ui(app: &mut App, ctx: &egui::Context) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Duelo 1 vs 1").size(40.0));
+      ui.label(RichText::new("La misma pregunta para los dos: el primero en acertar tira el dado contra su rival").size(15.0));
+      ui.label(RichText::new(format!("Jugando como: {}", app.player_name)).size(15.0));
+    });
+    ui.add_space(50.0);
+
+    let can_play = !app.player_name.trim().is_empty();
+    ui.columns(3, |columns| {
+      columns[0].vertical_centered(|ui| {
+        ui.label(RichText::new("Duelo local").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.label(RichText::new("Rival").size(15.0));
+        ui.add_sized(egui::vec2(250.0, 25.0), egui::TextEdit::singleline(&mut app.duel_settings.rival_name));
+        ui.add_space(15.0);
+        let can_play_local = can_play && !app.duel_settings.rival_name.trim().is_empty();
+        let local_clicked = ui.add_enabled_ui(can_play_local, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Jugar").size(15.0))).clicked()
+        }).inner;
+        if local_clicked {
+          let mut duel = DuelMatch::new(&app.bank, [app.player_name.trim(), app.duel_settings.rival_name.trim()]);
+          duel.begin();
+          app.duel = Duel::Local(Box::new(duel));
+          app.screen = CurrentScreen::Duel;
+        }
+      });
+
+      columns[1].vertical_centered(|ui| {
+        ui.label(RichText::new("Crear duelo").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.horizontal(|ui| {
+          ui.add_space(90.0);
+          ui.label(RichText::new("Puerto").size(15.0));
+          ui.add(egui::DragValue::new(&mut app.duel_settings.port).range(1024..=65535));
+        });
+        ui.add_space(15.0);
+        let create_clicked = ui.add_enabled_ui(can_play, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Crear").size(15.0))).clicked()
+        }).inner;
+        if create_clicked {
+          match DuelHost::start(app.duel_settings.port, app.player_name.trim(), &app.bank) {
+            Ok(host) => {
+              app.duel = Duel::Host(Box::new(host));
+              app.screen = CurrentScreen::Duel;
+            }
+            Err(e) => app.duel_settings.error = Some(format!("No se pudo crear el duelo: {}", e)),
+          }
+        }
+      });
+
+      columns[2].vertical_centered(|ui| {
+        ui.label(RichText::new("Unirse a un duelo").size(25.0).color(WHITE));
+        ui.add_space(15.0);
+        ui.add_sized(egui::vec2(250.0, 25.0), egui::TextEdit::singleline(&mut app.duel_settings.address));
+        ui.add_space(15.0);
+        let join_clicked = ui.add_enabled_ui(can_play, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Unirse").size(15.0))).clicked()
+        }).inner;
+        if join_clicked {
+          match DuelClient::join(app.duel_settings.address.trim(), app.player_name.trim()) {
+            Ok(client) => {
+              app.duel = Duel::Client(client);
+              app.screen = CurrentScreen::Duel;
+            }
+            Err(e) => app.duel_settings.error = Some(format!("No se pudo conectar a {}: {}", app.duel_settings.address, e)),
+          }
+        }
+      });
+    });
+
+    ui.add_space(30.0);
+    ui.vertical_centered(|ui| {
+      if let Some(error) = &app.duel_settings.error {
+        ui.label(RichText::new(error).size(15.0).color(Color32::RED));
+        ui.add_space(15.0);
+      }
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
 }
 
-impl AnalysisData {
-  pub fn get_hit_percentage(&self) -> f32 {
-    (self.correct_answers as f32 / self.total_quiz as f32) * 100.0
-  }
-}
+fn duel_ui(app: &mut App, ctx: &egui::Context) {
+  app.duel.update();
+  let snapshot = app.duel.snapshot().cloned();
+  let mut leave = false;
 
-impl Default for AnalysisData {
-  fn default() -> Self {
-    AnalysisData {
-      correct_answers: 0,
-      wrong_answers: 0,
-      win_streak: (0, 0),
-      total_quiz: 0,
-      best_hit: 0
+  match snapshot {
+    None => {
+      CentralPanel::default().show(ctx, |ui| {
+        ui.centered_and_justified(|ui| {
+          let message = if app.duel.is_connected() { "Conectando con el duelo..." } else { "No se pudo entrar al duelo" };
+          ui.label(RichText::new(message).size(25.0));
+        });
+      });
+      TopBottomPanel::bottom("bottom_panel_duel_connecting").show(ctx, |ui| {
+        if ui.button("Salir").clicked() {
+          leave = true;
+        }
+      });
     }
+    Some(snapshot) => match snapshot.status {
+      DuelStatus::Lobby => duel_lobby_ui(app, ctx, &snapshot, &mut leave),
+      DuelStatus::Question | DuelStatus::RoundOver => duel_battle_ui(app, ctx, &snapshot, &mut leave),
+      DuelStatus::Finished => duel_results_ui(app, ctx, &snapshot, &mut leave),
+    },
   }
-}
-
-pub struct Quiz {
-  pub quiz_items: Vec<QuizItem>,
-  pub current_quiz: QuizItem,
-  pub used_quiz_items: [u8; 40],
-  pub used_quiz_idx: usize,
-  pub duration: Duration,
-  pub start_time: Instant,
-}
-
-impl Default for Quiz {
-  fn default() -> Self {
-    let json_str = std::fs::read_to_string("assets/data/questions.json").unwrap();
-    let quiz_items: Vec<QuizItem> = serde_json::from_str(&json_str).unwrap();
-    let rng = rand::thread_rng().gen_range(0..quiz_items.len());
-    let quiz = quiz_items[rng].clone();
-    let used_quiz_items: [u8; 40] = [rng as u8; 40];
-    let duration = match quiz.tipo_reactivo.as_str() {
-      "Opción Múltiple" => Duration::from_secs(31),
-      "Verdadero o Falso" => Duration::from_secs(16),
-      "Ejercicio" => Duration::from_secs(300),
-      _ => Duration::from_secs(0)
-    };
 
-    Quiz {
-      quiz_items: quiz_items,
-      current_quiz: quiz,
-      used_quiz_items: used_quiz_items,
-      used_quiz_idx: 1,
-      duration,
-      start_time: Instant::now(),
-    }
+  if leave {
+    app.duel = Duel::Inactive;
+    app.screen = CurrentScreen::Menu;
   }
 }
 
-pub struct HealthStatus {
-  pub enemy_health: f32,
-  pub hero_health: f32,
-}
+fn duel_lobby_ui(app: &mut App, ctx: &egui::Context, snapshot: &DuelSnapshot, leave: &mut bool) {
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Sala de espera").size(40.0));
+      if let Duel::Host(host) = &app.duel {
+        let port = host.local_addr().port();
+        let ip = host.lan_ip.map(|ip| ip.to_string()).unwrap_or_else(|| String::from("127.0.0.1"));
+        ui.label(RichText::new(format!("Tu rival se une a {}:{}", ip, port)).size(15.0));
+      }
+      if !app.duel.is_connected() {
+        ui.label(RichText::new("Se perdió la conexión con el anfitrión").size(15.0).color(Color32::RED));
+      }
+      ui.add_space(30.0);
+      for fighter in &snapshot.fighters {
+        if fighter.name.is_empty() {
+          ui.label(RichText::new("Esperando rival...").size(20.0));
+        } else {
+          ui.label(RichText::new(&fighter.name).size(20.0).color(WHITE));
+        }
+      }
+      ui.add_space(30.0);
 
-impl Default for HealthStatus {
-  fn default() -> Self {
-    HealthStatus {
-      enemy_health: 1.0, 
-      hero_health: 1.0
-    }
-  }
+      if let Duel::Host(host) = &mut app.duel {
+        let has_rival = host.has_rival();
+        let begin_clicked = ui.add_enabled_ui(has_rival, |ui| {
+          ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Comenzar duelo").size(15.0))).clicked()
+        }).inner;
+        if begin_clicked {
+          host.begin();
+        }
+      } else {
+        ui.label(RichText::new("Esperando a que el anfitrión comience...").size(18.0));
+      }
+      ui.add_space(10.0);
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Salir").size(15.0))).clicked() {
+        *leave = true;
+      }
+    });
+  });
 }
 
-enum CurrentScreen {
-  Menu,
-  Ingame,
-  Analisis,
-}
+fn duel_battle_ui(app: &mut App, ctx: &egui::Context, snapshot: &DuelSnapshot, leave: &mut bool) {
+  let local_side = app.duel.local_side();
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct QuizItem {
-  #[serde(rename = "Unidad Temática")]
-  unidad_tematica: String,
-  
-  #[serde(rename = "Pregunta")]
-  pregunta: String,
-  
-  #[serde(rename = "Respuestas")]
-  pub respuestas: HashMap<String, String>,
-  
-  #[serde(rename = "Respuesta correcta")]
-  pub respuesta_correcta: String,
-  
-  #[serde(rename = "Tipo de reactivo")]
-  pub tipo_reactivo: String,
-}
+  TopBottomPanel::top("top_panel_duel")
+    .min_height(15.)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.horizontal(|ui| {
+        ui.label(RichText::new(format!("Ronda {}", snapshot.round)).size(15.0).color(WHITE));
+        ui.add_space(20.0);
+        if let Some(question) = &snapshot.question {
+          ui.label(RichText::new(&question.unidad_tematica).size(15.0));
+        }
+        if local_side.is_none() {
+          ui.add_space(20.0);
+          ui.label(RichText::new(format!(
+            "{}: teclas 1-4 · {}: teclas 7-0",
+            snapshot.fighters[0].name, snapshot.fighters[1].name
+          )).size(15.0).color(Color32::GOLD));
+        }
+        if !app.duel.is_connected() {
+          ui.add_space(20.0);
+          ui.label(RichText::new("Desconectado").size(15.0).color(Color32::RED));
+        }
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+          if ui.button("Abandonar").clicked() {
+            *leave = true;
+          }
+        });
+      });
+      ui.separator();
+  });
 
-impl Clone for QuizItem {
-  fn clone(&self) -> Self {
-    QuizItem {
-      unidad_tematica: self.unidad_tematica.clone(),
-      pregunta: self.pregunta.clone(),
-      respuestas: self.respuestas.clone(),
-      respuesta_correcta: self.respuesta_correcta.clone(),
-      tipo_reactivo: self.tipo_reactivo.clone()
-    }
-  }
-}
+  TopBottomPanel::bottom("bottom_panel_duel")
+    .min_height(250.0)
+    .resizable(false)
+    .show_separator_line(false)
+    .show(ctx, |ui| {
+      ui.separator();
+      components::duel_answers(ui, app, snapshot);
+  });
 
-impl App {
-  pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-    Self {
-      screen: CurrentScreen::Menu,
-      quiz: Quiz::default(),
-      health: HealthStatus::default(),
-      session_data: AnalysisData::default(),
-      rnd_animation: RndNumberAnimation::default(),
-      streak: StreakState::default(),
-    }
+  // Ambos lados usan la misma barra de vida y el mismo héroe, uno en espejo
+  for (side, fighter) in snapshot.fighters.iter().enumerate() {
+    let panel = if side == 0 { SidePanel::left("left_panel_duel") } else { SidePanel::right("right_panel_duel") };
+    panel
+      .min_width(350.0)
+      .resizable(false)
+      .show_separator_line(false)
+      .show(ctx, |ui| {
+        ui.add_space(5.0);
+        ui.vertical_centered(|ui| {
+          let color = if local_side == Some(side) { Color32::GOLD } else { WHITE };
+          ui.label(RichText::new(&fighter.name).size(18.0).color(color));
+          components::health_bar(ui, fighter.health, side == 1);
+          ui.add_space(10.0);
+          let status = if snapshot.status == DuelStatus::Question && snapshot.answered[side] { "Ya respondió" } else { "" };
+          ui.label(RichText::new(status).size(15.0));
+          ui.add_space(60.0);
+          let uv = if side == 0 {
+            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0))
+          } else {
+            egui::Rect::from_min_max(egui::pos2(1.0, 0.0), egui::pos2(0.0, 1.0))
+          };
+          ui.add(
+            Image::new(assets::image(assets::HERO))
+            .uv(uv)
+            .max_width(180.0)
+            .max_height(180.0)
+          );
+        });
+    });
   }
-}
 
-impl eframe::App for App {
-  fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-    match self.screen {
-      CurrentScreen::Menu => menu_ui(self, ctx),
-      CurrentScreen::Ingame => ingame_ui(self, ctx),
-      CurrentScreen::Analisis => analisis_ui(self, ctx),
-    }
-    if !self.rnd_animation.is_animating {
-      ctx.request_repaint_after(Duration::from_millis(250));
-    }
-  }
+  CentralPanel::default().show(ctx, |ui| {
+    ui.vertical_centered(|ui| {
+      let secs = app.duel.remaining().as_secs();
+      ui.heading(RichText::new(format!("{:02}:{:02}", secs / 60, secs % 60)).size(50.).color(WHITE));
+      ui.add_space(10.0);
+      if let Some(result) = &snapshot.result {
+        let text = match result.winner {
+          Some(side) => {
+            let ms = result.answers[side].as_ref().map_or(0, |(_, ms)| *ms);
+            format!(
+              "{} acertó más rápido, en {:.1} s, y sacó {}",
+              snapshot.fighters[side].name, ms as f32 / 1000.0, result.roll
+            )
+          }
+          None if result.answers.iter().flatten().any(|(key, _)| *key == result.correct_key) => {
+            String::from("Empate: los dos acertaron en el mismo tiempo")
+          }
+          None => String::from("Nadie acertó esta ronda"),
+        };
+        ui.label(RichText::new(text).size(18.0).color(WHITE));
+      } else if local_side.is_some_and(|side| snapshot.answered[side]) {
+        ui.label(RichText::new("Esperando al rival...").size(18.0));
+      }
+    });
+    ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+      if let Some(question) = &snapshot.question {
+        components::quiz_block(ui, &question.pregunta, 25., Some(WHITE));
+        components::question_image(ui, question);
+      }
+    });
+  });
 }
 
-fn menu_ui(app: &mut App, ctx: &egui::Context) {
-  SidePanel::left("left_panel_menu")
-    .min_width(600.0)
+fn duel_results_ui(app: &App, ctx: &egui::Context, snapshot: &DuelSnapshot, leave: &mut bool) {
+  let winner = snapshot.winner();
+  SidePanel::left("left_duel_results_panel")
     .resizable(false)
-    .show_separator_line(false)
+    .exact_width(400.0)
     .show(ctx, |ui| {
-    ui.vertical_centered(|ui| {
-      ui.add(
-        Image::new(egui::include_image!("../assets/img/logo.png"))
-        .max_width(200.0)
-        .max_height(200.0)
-      );
-      ui.label(RichText::new("CALABOZOS Y PREGUNTONES")
-        .family(egui::FontFamily::Name("CustomFont_1".into()))
-        .size(60.0)
-        .color(WHITE)
-      );
-    })
+      let title = match (app.duel.local_side(), winner) {
+        (Some(side), Some(winner)) if side == winner => String::from("VICTORIA"),
+        (Some(_), _) => String::from("DERROTA"),
+        (None, Some(winner)) => format!("GANA {}", snapshot.fighters[winner].name.to_uppercase()),
+        (None, None) => String::from("EMPATE"),
+      };
+      ui.centered_and_justified(|ui| {
+        ui.label(RichText::new(title).family(egui::FontFamily::Name("CustomFont_1".into())).size(50.0))
+      });
   });
-  
+
   CentralPanel::default().show(ctx, |ui| {
-    ui.centered_and_justified(|ui| {
-      ui.vertical_centered(|ui| {
-        if ui.add_sized(egui::vec2(200.0, 50.0), egui::Button::new("Iniciar")).clicked() {
-          app.screen = CurrentScreen::Ingame;
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Resultados del duelo").size(40.0));
+      if let Some(fighter) = snapshot.fighters.iter().find(|fighter| !fighter.connected) {
+        ui.label(RichText::new(format!("{} abandonó el duelo", fighter.name)).size(15.0));
+      }
+    });
+    ui.add_space(60.0);
+    egui::Grid::new("duel_results_table")
+      .spacing([30.0, 20.0])
+      .show(ui, |ui| {
+        for header in ["Jugador", "Aciertos", "Errores", "Daño infligido", "Vida restante"] {
+          ui.label(RichText::new(header).size(18.0).color(WHITE));
+        }
+        ui.end_row();
+        for fighter in &snapshot.fighters {
+          ui.label(RichText::new(&fighter.name).size(18.0));
+          ui.label(RichText::new(fighter.correct.to_string()).size(18.0));
+          ui.label(RichText::new(fighter.wrong.to_string()).size(18.0));
+          ui.label(RichText::new(format!("{} pts", fighter.damage_dealt)).size(18.0));
+          ui.label(RichText::new(format!("{}%", (fighter.health * 100.0) as u8)).size(18.0));
+          ui.end_row();
         }
       });
-    });
+    ui.add_space(50.0);
+    if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+      *leave = true;
+    }
   });
 }
 
@@ -219,8 +2310,17 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
   } else {
     app.quiz.duration - app.quiz.start_time.elapsed()
   };
-  if app.health.enemy_health == 0.0 || app.health.hero_health == 0.0 {
-    app.screen = CurrentScreen::Analisis;
+  if app.is_run_over() {
+    app.finish_run();
+  } else if app.player().health.enemy_health == 0.0 {
+    match app.mode {
+      GameMode::Infinito => {
+        let player = app.player_mut();
+        player.session_data.floors_cleared += 1;
+        player.health.spawn_enemy(player.session_data.floors_cleared);
+      }
+      _ => app.finish_run(),
+    }
   };
 
   TopBottomPanel::top("top_panel_ingame")
@@ -234,6 +2334,25 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
           RichText::new(&app.quiz.current_quiz.unidad_tematica)
             .size(15.0)
         );
+        if app.mode == GameMode::Infinito {
+          ui.add_space(20.0);
+          ui.label(
+            RichText::new(format!("Piso {}", app.player().session_data.floors_cleared + 1))
+              .size(15.0)
+              .color(Color32::GOLD)
+          );
+        }
+        if app.mode == GameMode::HotSeat {
+          for (idx, player) in app.players.iter().enumerate() {
+            ui.add_space(20.0);
+            let color = if idx == app.turn { Color32::GOLD } else if player.is_alive() { WHITE } else { Color32::DARK_GRAY };
+            ui.label(
+              RichText::new(format!("{} {}%", player.name, (player.health.hero_health * 100.0).round()))
+                .size(15.0)
+                .color(color)
+            );
+          }
+        }
         ui.add_space(ui.available_width() - 60.);            
         if ui.add_sized(egui::vec2(25.0, 10.0), egui::Button::new("☰ Menu")).clicked() {
           println!("Botón clicado!");
@@ -249,7 +2368,11 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
     .show_separator_line(false)
     .show(ctx, |ui| {
       ui.separator();
-      components::question_mode_1(ui, app);
+      if app.feedback.is_some() {
+        components::answer_feedback(ui, app);
+      } else {
+        components::question_mode_1(ui, app);
+      }
   });
 
   SidePanel::left("left_panel_ingame")
@@ -259,8 +2382,11 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
     .show(ctx, |ui| {
       ui.add_space(5.0);
       ui.vertical_centered(|ui| {
-        components::health_bar(ui, app.health.hero_health, false);
-        match app.streak {
+        if app.players.len() > 1 {
+          ui.label(RichText::new(format!("Turno de {}", app.player().name)).size(18.0).color(Color32::GOLD));
+        }
+        components::health_bar(ui, app.player().health.hero_health, false);
+        match app.player().streak {
           StreakState::NoStreak => {ui.add_space(120.0);}
           StreakState::X2 => {
             ui.add(
@@ -280,7 +2406,7 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
           }
         }
         ui.add(
-          Image::new(egui::include_image!("../assets/img/hero.png"))
+          Image::new(assets::image(assets::HERO))
           .max_width(180.0)
           .max_height(180.0)
         );
@@ -294,13 +2420,31 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
     .show(ctx, |ui| {
       ui.add_space(5.);
       ui.vertical_centered(|ui| {
-        components::health_bar(ui, app.health.enemy_health, true);
-        ui.add_space(150.0);
-        ui.add(
-          Image::new(egui::include_image!("../assets/img/enemy_1.png"))
-          .max_width(150.0)
-          .max_height(150.0)
-        );
+        match app.target_idx() {
+          Some(idx) => {
+            let target = &app.players[idx];
+            ui.label(RichText::new(format!("Objetivo: {}", target.name)).size(18.0).color(Color32::RED));
+            components::health_bar(ui, target.health.hero_health, true);
+            ui.add_space(120.0);
+            ui.add(
+              Image::new(assets::image(assets::HERO))
+              .max_width(180.0)
+              .max_height(180.0)
+            );
+          }
+          None => {
+            if app.players.len() > 1 {
+              ui.label(RichText::new("Enemigo compartido").size(18.0).color(Color32::RED));
+            }
+            components::health_bar(ui, app.player().health.enemy_health, true);
+            ui.add_space(150.0);
+            ui.add(
+              Image::new(assets::image(assets::ENEMY))
+              .max_width(150.0)
+              .max_height(150.0)
+            );
+          }
+        }
       });
   });
 
@@ -308,12 +2452,15 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
     if app.rnd_animation.is_animating {
       components::rand_num_animation(ui, app, ctx);
     }else {
-      components::timer(ui, app, remaining);
+      match app.mode {
+        GameMode::Practica => components::practice_progress(ui, app),
+        GameMode::Examen => components::exam_timer(ui, app, remaining),
+        _ => components::timer(ui, app, remaining),
+      }
       
       ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
-        ui.label(egui::RichText::new(&app.quiz.current_quiz.pregunta)
-        .size(30.)
-        .color(WHITE));
+        components::quiz_block(ui, &app.quiz.current_quiz.pregunta, 30., Some(WHITE));
+        components::question_image(ui, &app.quiz.current_quiz);
       });
     }
   });
@@ -321,11 +2468,32 @@ fn ingame_ui(app: &mut App, ctx: &egui::Context) {
 
 
 fn analisis_ui(app: &mut App, ctx: &egui::Context) {
+  if app.mode == GameMode::Examen {
+    exam_report_ui(app, ctx);
+    return;
+  }
+  if app.mode == GameMode::HotSeat {
+    hot_seat_results_ui(app, ctx);
+    return;
+  }
+
   SidePanel::left("left_results_panel")
     .resizable(false)
     .exact_width(600.0)
     .show(ctx, |ui| {
-      match app.health.hero_health {
+      match app.player().health.hero_health {
+        _ if app.mode == GameMode::Practica => {
+          ui.centered_and_justified(|ui| {
+            ui.label(RichText::new("PRÁCTICA").family(egui::FontFamily::Name("CustomFont_1".into())).size(50.0))
+          })
+        }
+        0.0 if app.mode == GameMode::Infinito => {
+          ui.centered_and_justified(|ui| {
+            ui.label(RichText::new(format!("PISO {}", app.player().session_data.floors_cleared + 1))
+              .family(egui::FontFamily::Name("CustomFont_1".into()))
+              .size(50.0))
+          })
+        }
         0.0 => {
           ui.centered_and_justified(|ui| {
             ui.label(RichText::new("PERDISTE").family(egui::FontFamily::Name("CustomFont_1".into())).size(50.0))
@@ -339,10 +2507,11 @@ fn analisis_ui(app: &mut App, ctx: &egui::Context) {
       };
   });
   CentralPanel::default().show(ctx, |ui| {
-    let lost_health = 1.0 - app.health.hero_health;
+    let lost_health = 1.0 - app.player().health.hero_health;
     let lost_health = (lost_health * 100.0) as u8;
 
-    let hit_percentage = app.session_data.get_hit_percentage().floor();
+    let session_data = &app.players[0].session_data;
+    let hit_percentage = session_data.get_hit_percentage().floor();
 
       ui.add_space(20.0);
       ui.vertical_centered(|ui| {
@@ -354,42 +2523,341 @@ fn analisis_ui(app: &mut App, ctx: &egui::Context) {
       .spacing([40.0, 25.0])
       .show(ui, |ui| {
         ui.vertical_centered(|ui| {ui.label(RichText::new("Cantidad de aciertos").size(18.0))});
-        ui.label(RichText::new(app.session_data.correct_answers.to_string()).size(18.0));
+        ui.label(RichText::new(session_data.correct_answers.to_string()).size(18.0));
         ui.end_row();
   
         ui.vertical_centered(|ui| {ui.label(RichText::new("Numero de respuestas erroneas").size(18.0))});
-        ui.label(RichText::new(app.session_data.wrong_answers.to_string()).size(18.0));
+        ui.label(RichText::new(session_data.wrong_answers.to_string()).size(18.0));
         ui.end_row();
         
         ui.vertical_centered(|ui| {ui.label(RichText::new("Procentaje de aciertos").size(18.0))});
-        ui.label(RichText::new(format!("{}%",hit_percentage.to_string())).size(18.0));
+        ui.label(RichText::new(format!("{}%",hit_percentage)).size(18.0));
         ui.end_row();
         
         ui.vertical_centered(|ui| {ui.label(RichText::new("Mejor racha de aciertos").size(18.0))});
-        ui.label(RichText::new(app.session_data.win_streak.0.to_string()).size(18.0));
+        ui.label(RichText::new(session_data.win_streak.0.to_string()).size(18.0));
         ui.end_row();
         
-        ui.vertical_centered(|ui| {ui.label(RichText::new("Mayor daño inflingido").size(18.0))});
-        ui.label(RichText::new(format!("{} pts",app.session_data.best_hit)).size(18.0));
-        ui.end_row();
-        
-        ui.vertical_centered(|ui| {ui.label(RichText::new("Vida total perdida").size(18.0))});
-        ui.label(RichText::new(format!("{}%",lost_health.to_string())).size(18.0));
-        ui.end_row();
+        if app.mode != GameMode::Practica {
+          ui.vertical_centered(|ui| {ui.label(RichText::new("Mayor daño inflingido").size(18.0))});
+          ui.label(RichText::new(format!("{} pts",session_data.best_hit)).size(18.0));
+          ui.end_row();
+          
+          ui.vertical_centered(|ui| {ui.label(RichText::new("Vida total perdida").size(18.0))});
+          ui.label(RichText::new(format!("{}%",lost_health)).size(18.0));
+          ui.end_row();
+        }
+
+        if app.mode == GameMode::Infinito {
+          ui.vertical_centered(|ui| {ui.label(RichText::new("Pisos superados").size(18.0))});
+          ui.label(RichText::new(session_data.floors_cleared.to_string()).size(18.0));
+          ui.end_row();
+
+          ui.vertical_centered(|ui| {ui.label(RichText::new("Preguntas contestadas").size(18.0))});
+          ui.label(RichText::new(session_data.total_quiz.to_string()).size(18.0));
+          ui.end_row();
+        }
       });
       ui.add_space(50.0);
       ui.vertical_centered(|ui| {
-        if ui.add_sized(
-          egui::vec2(250.0, 45.0),
-          egui::Button::new(RichText::new("Volver a jugar").size(15.0))
-        ).clicked() {
-          app.health = HealthStatus::default();
-          app.quiz = Quiz::default();
-          app.session_data = AnalysisData::default();
-          app.rnd_animation = RndNumberAnimation::default();
-          app.streak = StreakState::default();
-          app.screen = CurrentScreen::Ingame;
-        };
+        match app.mode {
+          GameMode::Normal | GameMode::Infinito => {
+            if ui.add_sized(
+              egui::vec2(250.0, 45.0),
+              egui::Button::new(RichText::new("Volver a jugar").size(15.0))
+            ).clicked() {
+              app.start_run(app.mode);
+            };
+          }
+          GameMode::Practica => {
+            let missed = app.practice.missed.len();
+            if missed > 0 && ui.add_sized(
+              egui::vec2(250.0, 45.0),
+              egui::Button::new(RichText::new(format!("Repasar falladas ({})", missed)).size(15.0))
+            ).clicked() {
+              app.start_practice_retry();
+            };
+            ui.add_space(10.0);
+            if ui.add_sized(
+              egui::vec2(250.0, 45.0),
+              egui::Button::new(RichText::new("Volver al menú").size(15.0))
+            ).clicked() {
+              app.screen = CurrentScreen::Menu;
+            };
+          }
+          // El reto diario solo tiene un intento por día; el examen y el
+          // multijugador tienen su propia pantalla de resultados
+          GameMode::RetoDiario(_) | GameMode::Examen | GameMode::HotSeat => {
+            if ui.add_sized(
+              egui::vec2(250.0, 45.0),
+              egui::Button::new(RichText::new("Volver al menú").size(15.0))
+            ).clicked() {
+              app.screen = CurrentScreen::Menu;
+            };
+          }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+          ui.add_space(10.0);
+          if ui.add_sized(
+            egui::vec2(250.0, 45.0),
+            egui::Button::new(RichText::new("Exportar resultados").size(15.0))
+          ).clicked() {
+            app.export_results();
+          };
+        }
+      });
+  });
+}
+fn exam_report_ui(app: &mut App, ctx: &egui::Context) {
+  SidePanel::left("left_exam_panel")
+    .resizable(false)
+    .exact_width(400.0)
+    .show(ctx, |ui| {
+      ui.add_space(200.0);
+      ui.vertical_centered(|ui| {
+        ui.label(RichText::new("CALIFICACIÓN").family(egui::FontFamily::Name("CustomFont_1".into())).size(40.0));
+        ui.add_space(20.0);
+        let grade = app.exam.grade();
+        let color = if grade >= 6.0 { Color32::GREEN } else { Color32::RED };
+        ui.label(RichText::new(format!("{:.1}", grade)).family(egui::FontFamily::Name("CustomFont_1".into())).size(80.0).color(color));
+        ui.add_space(20.0);
+        ui.label(RichText::new(format!(
+          "{} de {} aciertos",
+          app.exam.correct_answers(),
+          app.exam.answers.len()
+        )).size(18.0));
+        let used = app.exam.time_used.as_secs();
+        ui.label(RichText::new(format!("Tiempo utilizado: {:02}:{:02}", used / 60, used % 60)).size(18.0));
+      });
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Reporte del examen").size(40.0))
+    });
+    ui.add_space(20.0);
+
+    egui::Grid::new("exam_units_table")
+      .spacing([40.0, 10.0])
+      .show(ui, |ui| {
+        for (unit, correct, total) in app.exam.per_unit() {
+          ui.label(RichText::new(unit).size(15.0));
+          ui.label(RichText::new(format!("{} / {}", correct, total)).size(15.0));
+          ui.end_row();
+        }
+      });
+    ui.add_space(10.0);
+    ui.separator();
+
+    egui::ScrollArea::vertical().max_height(340.0).show(ui, |ui| {
+      for (idx, answer) in app.exam.answers.iter().enumerate() {
+        let (mark, color) = if answer.is_correct() { ("✔", Color32::GREEN) } else { ("✘", Color32::RED) };
+        let chosen = answer.chosen.as_ref()
+          .and_then(|key| answer.item.respuestas.get(key))
+          .map(String::as_str)
+          .unwrap_or("Sin respuesta");
+        let correct = answer.item.respuestas.get(&answer.item.respuesta_correcta)
+          .map(String::as_str)
+          .unwrap_or_default();
+
+        ui.horizontal_wrapped(|ui| {
+          ui.label(RichText::new(format!("{} {}.", mark, idx + 1)).size(15.0).color(color));
+          ui.label(components::quiz_text(&answer.item.pregunta, 15.0, Some(WHITE)));
+        });
+        ui.label(components::quiz_text(&format!("Tu respuesta: {}", chosen), 13.0, None));
+        if !answer.is_correct() {
+          ui.label(components::quiz_text(&format!("Respuesta correcta: {}", correct), 13.0, None));
+        }
+        ui.add_space(8.0);
+      }
+    });
+
+    ui.add_space(15.0);
+    ui.horizontal(|ui| {
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Nuevo examen").size(15.0))).clicked() {
+        app.start_run(GameMode::Examen);
+      }
+      #[cfg(not(target_arch = "wasm32"))]
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Exportar resultados").size(15.0))).clicked() {
+        app.export_results();
+      }
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
+  });
+}
+
+fn hot_seat_results_ui(app: &mut App, ctx: &egui::Context) {
+  SidePanel::left("left_hot_seat_panel")
+    .resizable(false)
+    .exact_width(400.0)
+    .show(ctx, |ui| {
+      let title = if app.hot_seat.versus {
+        match app.players.iter().find(|player| player.is_alive()) {
+          Some(winner) => format!("GANA {}", winner.name.to_uppercase()),
+          None => String::from("EMPATE"),
+        }
+      } else if app.players.iter().any(|player| player.is_alive()) {
+        String::from("GANARON")
+      } else {
+        String::from("PERDIERON")
+      };
+      ui.centered_and_justified(|ui| {
+        ui.label(RichText::new(title).family(egui::FontFamily::Name("CustomFont_1".into())).size(50.0))
+      });
+  });
+
+  CentralPanel::default().show(ctx, |ui| {
+    ui.add_space(20.0);
+    ui.vertical_centered(|ui| {
+      ui.label(RichText::new("Resultados").size(40.0))
+    });
+    ui.add_space(60.0);
+
+    egui::Grid::new("hot_seat_results_table")
+      .min_col_width(120.0)
+      .spacing([30.0, 25.0])
+      .show(ui, |ui| {
+        ui.label("");
+        for player in &app.players {
+          ui.label(RichText::new(&player.name).size(18.0).color(WHITE));
+        }
+        ui.end_row();
+
+        type PlayerStat = fn(&Player) -> String;
+        let rows: [(&str, PlayerStat); 6] = [
+          ("Aciertos", |player| player.session_data.correct_answers.to_string()),
+          ("Errores", |player| player.session_data.wrong_answers.to_string()),
+          ("Porcentaje de aciertos", |player| format!("{}%", player.session_data.get_hit_percentage().floor())),
+          ("Mejor racha", |player| player.session_data.win_streak.0.to_string()),
+          ("Mayor daño", |player| format!("{} pts", player.session_data.best_hit)),
+          ("Vida restante", |player| format!("{}%", (player.health.hero_health * 100.0) as u8)),
+        ];
+        for (label, value) in rows {
+          ui.label(RichText::new(label).size(18.0));
+          for player in &app.players {
+            ui.label(RichText::new(value(player)).size(18.0));
+          }
+          ui.end_row();
+        }
       });
+
+    ui.add_space(50.0);
+    ui.horizontal(|ui| {
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver a jugar").size(15.0))).clicked() {
+        app.start_run(GameMode::HotSeat);
+      }
+      // Las respuestas de todos quedan en el mismo archivo, cada una con su jugador
+      #[cfg(not(target_arch = "wasm32"))]
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Exportar resultados").size(15.0))).clicked() {
+        app.export_results();
+      }
+      if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Volver al menú").size(15.0))).clicked() {
+        app.screen = CurrentScreen::Menu;
+      }
+    });
   });
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::quiz_item;
+
+  // Un error de captura en un Aiken no debe tumbar el banco completo
+  #[test]
+  fn aiken_typos_keep_the_valid_questions() {
+    let text = "¿Dos más dos?\nA. 3\nB. 4\nANSWER: B\n\n¿Sin respuesta?\nA. Sí\nB. No\n\n¿Capital de Perú?\nA. Lima\nB. Quito\nANSWER: Z\n";
+    let (items, warning) = aiken_file("data/repaso.txt", text);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].unidad_tematica, "repaso");
+    assert!(validate_bank(&items).is_ok());
+
+    let warning = warning.expect("se esperaba un aviso").to_string();
+    assert!(warning.contains("data/repaso.txt"), "{}", warning);
+    assert!(warning.contains("2 preguntas omitidas"), "{}", warning);
+    assert!(warning.contains("Línea 6: pregunta sin ANSWER"), "{}", warning);
+
+    let (items, warning) = aiken_file("data/bien.txt", "¿Uno?\nA. 1\nB. 2\nANSWER: A\n");
+    assert_eq!(items.len(), 1);
+    assert!(warning.is_none());
+  }
+
+  #[test]
+  fn wrong_answers_break_the_streak_and_hurt_the_hero() {
+    let mut player = Player::new("Ana");
+    for _ in 0..3 {
+      player.resolve_answer(true);
+      player.resolve_roll(5);
+    }
+    assert!(player.streak == StreakState::X2);
+    player.resolve_answer(false);
+    assert!(player.streak == StreakState::NoStreak);
+    assert_eq!(player.session_data.win_streak, (3, 0));
+    assert_eq!(player.session_data.wrong_answers, 1);
+    assert!(player.health.hero_health < 1.0);
+
+    let health = player.health.hero_health;
+    player.resolve_timeout();
+    assert!(player.health.hero_health < health);
+  }
+
+  // El dado usa la racha con la que se contestó y la nueva queda para la siguiente
+  #[test]
+  fn rolls_apply_the_streak_multiplier() {
+    let mut player = Player::new("Ana");
+    let damages: Vec<usize> = (0..6)
+      .map(|_| {
+        player.resolve_answer(true);
+        player.resolve_roll(10)
+      })
+      .collect();
+    assert_eq!(damages, vec![10, 10, 10, 20, 20, 30]);
+    assert!(player.streak == StreakState::X3);
+    assert_eq!(player.session_data.best_hit, 30);
+    assert_eq!(player.session_data.correct_answers, 6);
+  }
+
+  fn numbered_bank(len: usize) -> Vec<QuizItem> {
+    (0..len).map(|idx| quiz_item("1 - Prueba", &format!("Pregunta {}", idx), &[("a", "Sí"), ("b", "No")], "a")).collect()
+  }
+
+  // Con más de 255 preguntas los índices no deben confundirse entre sí
+  #[test]
+  fn large_banks_do_not_repeat_questions() {
+    let mut quiz = Quiz::from_seed(numbered_bank(600), 7);
+    let mut asked = vec![quiz.current_quiz.pregunta.clone()];
+    for _ in 0..(MAX_USED_QUIZ_ITEMS - 1) {
+      quiz.next_question();
+      asked.push(quiz.current_quiz.pregunta.clone());
+    }
+    let mut unique = asked.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), asked.len());
+
+    // Al recargar sin la pregunta actual solo queda marcada la nueva
+    quiz.replace_items(numbered_bank(600).into_iter().rev().filter(|item| item.pregunta != quiz.current_quiz.pregunta).collect());
+    assert_eq!(quiz.used_quiz_items.len(), 1);
+    assert_eq!(quiz.quiz_items[quiz.used_quiz_items[0]].pregunta, quiz.current_quiz.pregunta);
+  }
+
+  #[test]
+  fn small_banks_cycle_without_repeating_the_current_question() {
+    let mut quiz = Quiz::from_seed(numbered_bank(3), 1);
+    for _ in 0..20 {
+      let previous = quiz.current_quiz.pregunta.clone();
+      quiz.next_question();
+      assert_ne!(quiz.current_quiz.pregunta, previous);
+    }
+  }
+
+  #[test]
+  fn included_bank_loads() {
+    let (items, _) = load_quiz_items().expect("el banco incluido es válido");
+    assert!(!items.is_empty());
+  }
+}
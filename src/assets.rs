@@ -0,0 +1,291 @@
+use std::{borrow::Cow, fmt, io, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
+use web_time::Instant;
+
+// Todos los archivos del juego van incluidos en el ejecutable, así funciona
+// sin importar desde qué carpeta se lance. Con `--assets <carpeta>` o la
+// variable CALABOZOS_ASSETS se puede indicar una carpeta con la misma
+// estructura (data/, font/, img/) cuyos archivos reemplazan a los incluidos
+// uno por uno. Sin ninguna de las dos se usa `assets/` dentro de la carpeta
+// de datos del usuario, que es donde guarda el editor. En el navegador solo
+// existen los incluidos.
+
+pub const DATA_DIR: &str = "data/";
+pub const QUESTIONS: &str = "data/questions.json";
+pub const TITLE_FONT: &str = "font/upheavtt.ttf";
+pub const TEXT_FONT: &str = "font/VCR_OSD_MONO_1.ttf";
+pub const LOGO: &str = "img/logo.png";
+pub const HERO: &str = "img/hero.png";
+pub const ENEMY: &str = "img/enemy_1.png";
+
+pub const OVERRIDE_ENV: &str = "CALABOZOS_ASSETS";
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+const EMBEDDED: &[(&str, &[u8])] = &[
+  (QUESTIONS, include_bytes!("../assets/data/questions.json")),
+  (TITLE_FONT, include_bytes!("../assets/font/upheavtt.ttf")),
+  (TEXT_FONT, include_bytes!("../assets/font/VCR_OSD_MONO_1.ttf")),
+  (LOGO, include_bytes!("../assets/img/logo.png")),
+  (HERO, include_bytes!("../assets/img/hero.png")),
+  (ENEMY, include_bytes!("../assets/img/enemy_1.png")),
+];
+
+#[cfg(not(target_arch = "wasm32"))]
+static OVERRIDE_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+// Debe llamarse al arrancar, antes de leer cualquier archivo; si no se llama
+// se usa la variable de entorno
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_override_dir(dir: Option<PathBuf>) {
+  let _ = OVERRIDE_DIR.set(dir.or_else(env_override_dir).or_else(default_dir));
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn env_override_dir() -> Option<PathBuf> {
+  std::env::var_os(OVERRIDE_ENV).map(PathBuf::from)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn default_dir() -> Option<PathBuf> {
+  Some(crate::profile::data_dir().join("assets"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn override_dir() -> Option<&'static Path> {
+  OVERRIDE_DIR.get_or_init(|| env_override_dir().or_else(default_dir)).as_deref()
+}
+
+// En depuración (`cargo run`) la carpeta assets/ del proyecto funciona como
+// una segunda carpeta de reemplazo, después de la principal: así los cambios
+// a los archivos incluidos se ven sin recompilar y se recargan solos
+#[cfg(not(target_arch = "wasm32"))]
+fn search_dirs() -> impl Iterator<Item = PathBuf> {
+  let source = cfg!(debug_assertions).then(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("assets"));
+  override_dir().map(Path::to_path_buf).into_iter().chain(source)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn override_path(name: &str) -> Option<PathBuf> {
+  search_dirs().map(|dir| dir.join(name)).find(|path| path.is_file())
+}
+
+fn embedded(name: &str) -> Option<&'static [u8]> {
+  EMBEDDED.iter().find(|(embedded, _)| *embedded == name).map(|(_, bytes)| *bytes)
+}
+
+// Errores al cargar un archivo del juego. Guardan la ruta (o el nombre del
+// archivo incluido) para poder decirle al usuario cuál hay que corregir.
+#[derive(Debug)]
+pub enum AssetError {
+  NotFound { path: String },
+  Io { path: String, error: io::Error },
+  Encoding { path: String },
+  Json { path: String, line: usize, column: usize, message: String },
+  Invalid { path: String, message: String },
+}
+
+impl AssetError {
+  pub fn path(&self) -> &str {
+    match self {
+      AssetError::NotFound { path }
+      | AssetError::Io { path, .. }
+      | AssetError::Encoding { path }
+      | AssetError::Json { path, .. }
+      | AssetError::Invalid { path, .. } => path,
+    }
+  }
+
+  pub fn json(path: String, error: &serde_json::Error) -> Self {
+    let (line, column) = (error.line(), error.column());
+    // serde_json agrega la posición al final del mensaje; aquí va aparte
+    let message = error.to_string();
+    let suffix = format!(" at line {} column {}", line, column);
+    let message = message.strip_suffix(&suffix).unwrap_or(&message).to_owned();
+    AssetError::Json { path, line, column, message }
+  }
+}
+
+impl fmt::Display for AssetError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      AssetError::NotFound { path } => write!(f, "No se encontró {}", path),
+      AssetError::Io { path, error } => write!(f, "No se pudo leer {}: {}", path, error),
+      AssetError::Encoding { path } => write!(f, "{} no está guardado como UTF-8", path),
+      AssetError::Json { path, line, column, message } => {
+        write!(f, "{} (línea {}, columna {}): {}", path, line, column, message)
+      }
+      AssetError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+    }
+  }
+}
+
+impl std::error::Error for AssetError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      AssetError::Io { error, .. } => Some(error),
+      _ => None,
+    }
+  }
+}
+
+// Ruta que se muestra en los errores: la del archivo de reemplazo si existe
+pub fn display_path(name: &str) -> String {
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(path) = override_path(name) {
+    return path.display().to_string();
+  }
+  format!("{} (incluido)", name)
+}
+
+pub fn read(name: &str) -> Result<Cow<'static, [u8]>, AssetError> {
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(path) = override_path(name) {
+    return std::fs::read(&path)
+      .map(Cow::Owned)
+      .map_err(|error| AssetError::Io { path: path.display().to_string(), error });
+  }
+  embedded(name)
+    .map(Cow::Borrowed)
+    .ok_or_else(|| AssetError::NotFound { path: name.to_owned() })
+}
+
+pub fn read_to_string(name: &str) -> Result<String, AssetError> {
+  String::from_utf8(read(name)?.into_owned()).map_err(|_| AssetError::Encoding { path: display_path(name) })
+}
+
+pub fn load_json<T: serde::de::DeserializeOwned>(name: &str) -> Result<T, AssetError> {
+  let json_str = read_to_string(name)?;
+  serde_json::from_str(&json_str).map_err(|e| AssetError::json(display_path(name), &e))
+}
+
+// Archivos disponibles dentro de una subcarpeta (p. ej. "data/"), sumando
+// los incluidos y los de la carpeta de reemplazo
+pub fn list(dir: &str) -> Vec<String> {
+  let mut names: Vec<String> = EMBEDDED.iter()
+    .map(|(name, _)| name.to_string())
+    .filter(|name| name.starts_with(dir))
+    .collect();
+
+  #[cfg(not(target_arch = "wasm32"))]
+  for entries in search_dirs().filter_map(|base| std::fs::read_dir(base.join(dir)).ok()) {
+    for entry in entries.flatten() {
+      let name = format!("{}{}", dir, entry.file_name().to_string_lossy());
+      if entry.path().is_file() && !names.contains(&name) {
+        names.push(name);
+      }
+    }
+  }
+  names.sort();
+  names
+}
+
+// Revisa cada cierto tiempo las fechas de modificación de una subcarpeta de
+// las carpetas de reemplazo (p. ej. "data/") para poder recargar sus archivos
+// con el juego abierto. Los archivos incluidos no cambian, así que en el
+// navegador no hace nada.
+pub struct FileWatcher {
+  dirs: Vec<PathBuf>,
+  snapshot: Vec<(PathBuf, Option<SystemTime>)>,
+  last_check: Instant,
+}
+
+impl FileWatcher {
+  pub fn new(dir: &str) -> Self {
+    let dirs = watch_dirs(dir);
+    let snapshot = dirs_snapshot(&dirs);
+    FileWatcher { dirs, snapshot, last_check: Instant::now() }
+  }
+
+  pub fn is_active(&self) -> bool {
+    !self.dirs.is_empty()
+  }
+
+  // true si algún archivo cambió, apareció o se borró desde la última revisión
+  pub fn poll(&mut self) -> bool {
+    if self.dirs.is_empty() || self.last_check.elapsed() < WATCH_INTERVAL {
+      return false;
+    }
+    self.last_check = Instant::now();
+    let snapshot = dirs_snapshot(&self.dirs);
+    if snapshot == self.snapshot {
+      return false;
+    }
+    self.snapshot = snapshot;
+    true
+  }
+}
+
+// Se vigila aunque la carpeta todavía no exista, por si se crea después
+#[cfg(not(target_arch = "wasm32"))]
+fn watch_dirs(dir: &str) -> Vec<PathBuf> {
+  search_dirs().map(|base| base.join(dir)).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn watch_dirs(_dir: &str) -> Vec<PathBuf> {
+  Vec::new()
+}
+
+fn dirs_snapshot(dirs: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+  dirs.iter().flat_map(|dir| dir_snapshot(dir)).collect()
+}
+
+fn dir_snapshot(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+  let mut snapshot: Vec<(PathBuf, Option<SystemTime>)> = std::fs::read_dir(dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|entry| {
+      let modified = entry.metadata().and_then(|metadata| metadata.modified()).ok();
+      (entry.path(), modified)
+    })
+    .collect();
+  snapshot.sort();
+  snapshot
+}
+
+// Imágenes de las preguntas. Las rutas son relativas a la carpeta del banco,
+// data/, y se buscan en las mismas carpetas que el banco. No van incluidas en
+// el ejecutable, así que en el navegador no hay. La ruta se resuelve una sola
+// vez y se guarda, porque se pide en cada cuadro.
+#[cfg(not(target_arch = "wasm32"))]
+static BANK_IMAGES: std::sync::Mutex<Option<std::collections::HashMap<String, Option<String>>>> = std::sync::Mutex::new(None);
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn bank_image(path: &str) -> Option<egui::ImageSource<'static>> {
+  let mut cache = BANK_IMAGES.lock().unwrap_or_else(|e| e.into_inner());
+  let uri = cache.get_or_insert_with(Default::default)
+    .entry(path.to_owned())
+    .or_insert_with(|| {
+      override_path(&format!("{}{}", DATA_DIR, path)).map(|path| format!("file://{}", path.display()))
+    })
+    .clone()?;
+  Some(egui::ImageSource::Uri(uri.into()))
+}
+
+// Al recargar el banco las imágenes pudieron aparecer o moverse
+#[cfg(not(target_arch = "wasm32"))]
+pub fn clear_image_cache() {
+  *BANK_IMAGES.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn bank_image(_path: &str) -> Option<egui::ImageSource<'static>> {
+  None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn clear_image_cache() {}
+
+pub fn image(name: &'static str) -> egui::ImageSource<'static> {
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(path) = override_path(name) {
+    return egui::ImageSource::Uri(format!("file://{}", path.display()).into());
+  }
+  egui::ImageSource::Bytes {
+    uri: format!("bytes://{}", name).into(),
+    bytes: embedded(name).unwrap_or_default().into(),
+  }
+}
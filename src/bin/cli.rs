@@ -0,0 +1,321 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::Path,
+    process::ExitCode,
+    time::Instant,
+};
+
+use calabozos_y_preguntones::{app::{bank_to_json, load_quiz_items, validate_bank, AnalysisData, QuizItem}, assets, csv, daily::DailyDate, import, markup, profile::Profiles, results::{self, AnswerRecord}, study};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use serde_json::json;
+
+// Modo sin interfaz para scripts: escribe cada pregunta como una línea JSON en
+// stdout, lee la clave de la respuesta de stdin y al final reporta los
+// resultados, también en JSON. Con --texto se muestra legible para practicar
+// desde una terminal.
+
+const USAGE: &str = "Uso: calabozos_cli [--banco RUTA] [--semilla N] [--unidades 1,2,...] [--preguntas N] [--texto] [--convertir SALIDA] [--resultados SALIDA] [--jugador NOMBRE] [--guia SALIDA [--perfil NOMBRE]]";
+
+struct Options {
+    bank: Option<String>,
+    seed: Option<u64>,
+    units: Vec<String>,
+    questions: Option<usize>,
+    text: bool,
+    convert: Option<String>,
+    results: Option<String>,
+    player: String,
+    guide: Option<String>,
+    profile: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        bank: None,
+        seed: None,
+        units: Vec::new(),
+        questions: None,
+        text: false,
+        convert: None,
+        results: None,
+        player: String::from("Jugador"),
+        guide: None,
+        profile: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("Falta el valor de {}", arg));
+        match arg.as_str() {
+            "--banco" => options.bank = Some(value()?),
+            "--semilla" => {
+                let seed = value()?;
+                options.seed = Some(seed.parse().map_err(|_| format!("Semilla inválida: {}", seed))?);
+            }
+            "--unidades" => {
+                options.units = value()?.split(',').map(|unit| unit.trim().to_owned()).collect();
+            }
+            "--preguntas" => {
+                let count = value()?;
+                let parsed = count.parse().ok().filter(|count: &usize| *count > 0);
+                options.questions = Some(parsed.ok_or_else(|| format!("Número de preguntas inválido: {} (debe ser al menos 1)", count))?);
+            }
+            "--texto" => options.text = true,
+            "--convertir" => options.convert = Some(value()?),
+            "--resultados" => options.results = Some(value()?),
+            "--jugador" => options.player = value()?,
+            "--guia" => options.guide = Some(value()?),
+            "--perfil" => options.profile = Some(value()?),
+            "-h" | "--help" => return Err(String::new()),
+            _ => return Err(format!("Opción desconocida: {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+// Una unidad se puede pedir por su nombre completo o solo por su número
+fn unit_matches(unit: &str, filter: &str) -> bool {
+    unit == filter || unit.split(" - ").next() == Some(filter)
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            if !e.is_empty() {
+                eprintln!("{}", e);
+            }
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    // Sin --banco se usa el banco incluido (o el de CALABOZOS_ASSETS)
+    let bank_name = options.bank.as_deref().unwrap_or(assets::QUESTIONS);
+    let quiz_items = match &options.bank {
+        Some(path) => load_bank_file(Path::new(path)),
+        None => load_quiz_items()
+            .map(|(items, warnings)| {
+                for warning in warnings {
+                    eprintln!("Aviso: {}", warning);
+                }
+                items
+            })
+            .map_err(|e| e.to_string()),
+    };
+    let quiz_items = match quiz_items {
+        Ok(quiz_items) => quiz_items,
+        Err(e) => {
+            eprintln!("No se pudo cargar el banco {}: {}", bank_name, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // --convertir escribe el banco ya cargado como JSON, o como CSV si la
+    // salida termina en .csv
+    if let Some(output) = &options.convert {
+        let contents = if output.to_lowercase().ends_with(".csv") {
+            csv::write(&quiz_items)
+        } else {
+            bank_to_json(&quiz_items)
+        };
+        return match fs::write(output, contents) {
+            Ok(()) => {
+                eprintln!("{} preguntas escritas en {}", quiz_items.len(), output);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("No se pudo escribir {}: {}", output, e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let mut items: Vec<QuizItem> = quiz_items.into_iter()
+        .filter(|item| options.units.is_empty() || options.units.iter().any(|unit| unit_matches(&item.unidad_tematica, unit)))
+        .collect();
+    if items.is_empty() {
+        eprintln!("No hay preguntas para esas unidades");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(output) = &options.guide {
+        return write_guide(output, items, options.profile.as_deref());
+    }
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    items.shuffle(&mut rng);
+    if let Some(count) = options.questions {
+        items.truncate(count);
+    }
+
+    let records = match run(&items, options.text, &options.player) {
+        Ok(records) => records,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // --resultados guarda el detalle de cada respuesta como JSON, o como CSV
+    // si la salida termina en .csv
+    if let Some(output) = &options.results {
+        let contents = if output.to_lowercase().ends_with(".csv") {
+            results::to_csv(&records)
+        } else {
+            results::to_json("Modo sin interfaz", &DailyDate::today().to_string(), &records)
+        };
+        if let Err(e) = fs::write(output, contents) {
+            eprintln!("No se pudo escribir {}: {}", output, e);
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+// --guia escribe la guía de estudio como PDF si la salida termina en .pdf o
+// como HTML si no. Con --perfil solo entran las preguntas que ese jugador
+// falló y todavía no ha contestado bien
+fn write_guide(output: &str, mut items: Vec<QuizItem>, profile: Option<&str>) -> ExitCode {
+    let title = match profile {
+        Some(name) => {
+            let profiles = Profiles::load();
+            let Some(profile) = profiles.get(name) else {
+                eprintln!("No existe el perfil {}", name);
+                return ExitCode::FAILURE;
+            };
+            items.retain(|item| profile.missed.contains(&item.pregunta));
+            if items.is_empty() {
+                eprintln!("{} no tiene preguntas falladas en este banco", name);
+                return ExitCode::FAILURE;
+            }
+            format!("Guía de estudio: preguntas falladas de {}", name)
+        }
+        None => String::from("Guía de estudio"),
+    };
+
+    let result = if output.to_lowercase().ends_with(".pdf") {
+        fs::write(output, study::pdf(&title, &items))
+    } else {
+        fs::write(output, study::html(&title, &items))
+    };
+    match result {
+        Ok(()) => {
+            eprintln!("{} preguntas escritas en {}", items.len(), output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("No se pudo escribir {}: {}", output, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Los .json se leen tal cual; los de Moodle (.xml, .gift) y CSV se convierten y
+// lo que no se pudo convertir se reporta en stderr. En los dos casos se revisa
+// igual que en el juego, para que lo que pase aquí también se pueda jugar.
+fn load_bank_file(path: &Path) -> Result<Vec<QuizItem>, String> {
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let items = if is_json {
+        let json_str = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&json_str).map_err(|e| e.to_string())?
+    } else {
+        let report = import::from_path(path)?;
+        eprintln!("{}", report.summary());
+        for skipped in &report.skipped {
+            eprintln!("  omitida {}: {}", skipped.name, skipped.reason);
+        }
+        report.items
+    };
+    validate_bank(&items)?;
+    Ok(items)
+}
+
+fn run(items: &[QuizItem], text: bool, player: &str) -> io::Result<Vec<AnswerRecord>> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock().lines();
+    let mut stdout = io::stdout().lock();
+    let mut data = AnalysisData::default();
+    let mut records = Vec::new();
+    let mut completed = true;
+
+    for (idx, item) in items.iter().enumerate() {
+        let mut answers: Vec<(&String, &String)> = item.respuestas.iter().collect();
+        answers.sort();
+        if text {
+            writeln!(stdout, "\n[{}/{}] {}", idx + 1, items.len(), item.unidad_tematica)?;
+            writeln!(stdout, "{}", markup::to_plain(&item.pregunta, true))?;
+            for (key, answer) in &answers {
+                writeln!(stdout, "  {}) {}", key, markup::to_plain(answer, true))?;
+            }
+            write!(stdout, "> ")?;
+        } else {
+            let line = json!({
+                "tipo": "pregunta",
+                "numero": idx + 1,
+                "total": items.len(),
+                "unidad": item.unidad_tematica,
+                "tipo_reactivo": item.tipo_reactivo,
+                "pregunta": item.pregunta,
+                "respuestas": item.respuestas,
+            });
+            writeln!(stdout, "{}", line)?;
+        }
+        stdout.flush()?;
+
+        let start = Instant::now();
+        let Some(key) = input.next().transpose()? else {
+            // Se acabó la entrada antes de contestar todo
+            completed = false;
+            break;
+        };
+        let key = key.trim();
+        // Las claves del banco se respetan, pero se acepta "v" por "V"
+        let key = item.respuestas.keys()
+            .find(|candidate| candidate.eq_ignore_ascii_case(key))
+            .map_or(key, String::as_str);
+        let correct = key == item.respuesta_correcta;
+        data.record_answer(correct);
+        let time_ms = start.elapsed().as_millis() as u64;
+        records.push(AnswerRecord::new(idx + 1, player, item, Some(key), time_ms));
+
+        if text {
+            if correct {
+                writeln!(stdout, "¡Correcto!")?;
+            } else {
+                let correct_answer = item.respuestas.get(&item.respuesta_correcta).map_or("", String::as_str);
+                writeln!(stdout, "Incorrecto: era {}) {}", item.respuesta_correcta, markup::to_plain(correct_answer, true))?;
+            }
+            if let Some(explicacion) = &item.explicacion {
+                writeln!(stdout, "{}", markup::to_plain(explicacion, true))?;
+            }
+        } else {
+            let line = json!({
+                "tipo": "respuesta",
+                "numero": idx + 1,
+                "clave": key,
+                "correcta": correct,
+                "respuesta_correcta": item.respuesta_correcta,
+                "explicacion": item.explicacion,
+                "tiempo_ms": time_ms,
+            });
+            writeln!(stdout, "{}", line)?;
+        }
+    }
+
+    if text {
+        let hit_percentage = if data.total_quiz == 0 { 0.0 } else { data.get_hit_percentage() };
+        writeln!(stdout, "\nAciertos: {} · Errores: {} · Mejor racha: {} · {:.0}%",
+            data.correct_answers, data.wrong_answers, data.win_streak.0, hit_percentage)?;
+    } else {
+        let line = json!({
+            "tipo": "resultados",
+            "completada": completed,
+            "resultados": data,
+        });
+        writeln!(stdout, "{}", line)?;
+    }
+    Ok(records)
+}
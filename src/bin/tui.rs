@@ -0,0 +1,259 @@
+use std::{io, process::ExitCode, time::Duration};
+
+use calabozos_y_preguntones::{app::{load_quiz_items, Player, Quiz, QuizItem, RndNumberAnimation, StreakState}, markup, profile::Profiles};
+use rand::Rng;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Alignment, Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Gauge, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+// Interfaz de terminal para los equipos del laboratorio sin GPU. La partida
+// usa las mismas piezas que la versión gráfica (Quiz, Player, dados y rachas,
+// y las reglas de `Player::resolve_*`); aquí solo cambia cómo se dibuja y
+// cómo se contesta (con el teclado).
+
+fn main() -> ExitCode {
+    // El banco se carga antes de tomar la terminal para que el error se lea
+    let bank = match load_quiz_items() {
+        Ok((bank, warnings)) => {
+            // Quedan en la terminal al salir del juego
+            for warning in warnings {
+                eprintln!("Aviso: {}", warning);
+            }
+            bank
+        }
+        Err(e) => {
+            eprintln!("No se pudo cargar el banco de preguntas: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, bank);
+    ratatui::restore();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[derive(PartialEq)]
+enum Phase {
+    Question,
+    Rolling,
+    Finished,
+}
+
+struct Game {
+    quiz: Quiz,
+    player: Player,
+    dice: RndNumberAnimation,
+    phase: Phase,
+    message: String,
+    profiles: Profiles,
+}
+
+impl Game {
+    fn new(bank: Vec<QuizItem>) -> Self {
+        Game {
+            quiz: Quiz::from_items(bank),
+            player: Player::new("Jugador"),
+            dice: RndNumberAnimation::default(),
+            phase: Phase::Question,
+            message: String::new(),
+            profiles: Profiles::load(),
+        }
+    }
+
+    // Las falladas quedan en el perfil para la guía de estudio, igual que en
+    // la versión gráfica
+    fn record_answer(&mut self, correct: bool) {
+        self.profiles.record_answer(&self.player.name, &self.quiz.current_quiz.pregunta, correct);
+    }
+
+    fn answer(&mut self, letter: char) {
+        if self.phase != Phase::Question {
+            return;
+        }
+        let Some(key) = self.quiz.current_quiz.respuestas.keys()
+            .find(|key| key.eq_ignore_ascii_case(&letter.to_string()))
+            .cloned() else { return };
+
+        let correct = key == self.quiz.current_quiz.respuesta_correcta;
+        self.player.resolve_answer(correct);
+        self.record_answer(correct);
+        if correct {
+            self.dice.start();
+            self.phase = Phase::Rolling;
+        } else {
+            let correct_key = &self.quiz.current_quiz.respuesta_correcta;
+            self.message = format!(
+                "Incorrecto: era {}) {}",
+                correct_key, markup::to_plain(&self.quiz.current_quiz.respuestas[correct_key], true)
+            );
+            self.next_question();
+        }
+    }
+
+    fn update(&mut self) {
+        match self.phase {
+            Phase::Question if self.quiz.remaining() == Duration::ZERO => {
+                self.message = String::from("Se acabó el tiempo");
+                self.player.resolve_timeout();
+                self.record_answer(false);
+                self.next_question();
+            }
+            Phase::Rolling => {
+                let elapsed = self.dice.animation_start.map_or(Duration::ZERO, |start| start.elapsed());
+                if elapsed < self.dice.duration {
+                    return;
+                }
+                let multiplier = self.player.streak.multiplier();
+                let damage = self.player.resolve_roll(self.dice.rolled_number);
+                self.player.health.damage_enemy(damage as f32 / 100.0);
+                self.message = match multiplier {
+                    1 => format!("¡Correcto! Sacaste {}", damage),
+                    _ => format!("¡Correcto! Sacaste {} x{} = {}", self.dice.rolled_number, multiplier, damage),
+                };
+                self.dice.is_animating = false;
+                self.dice.animation_start = None;
+                self.next_question();
+            }
+            _ => (),
+        }
+    }
+
+    fn next_question(&mut self) {
+        if !self.player.is_alive() || self.player.health.enemy_health == 0.0 {
+            self.phase = Phase::Finished;
+            return;
+        }
+        self.quiz.next_question();
+        self.phase = Phase::Question;
+    }
+}
+
+fn run(terminal: &mut DefaultTerminal, bank: Vec<QuizItem>) -> io::Result<()> {
+    let mut game = Game::new(bank);
+    loop {
+        game.update();
+        terminal.draw(|frame| draw(frame, &game))?;
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Esc => return Ok(()),
+            _ if game.phase == Phase::Finished => return Ok(()),
+            KeyCode::Char(letter) => game.answer(letter),
+            _ => (),
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, game: &Game) {
+    if game.phase == Phase::Finished {
+        draw_results(frame, game);
+        return;
+    }
+
+    let [header, bars, timer, question, answers, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(3),
+        Constraint::Min(4),
+        Constraint::Length(7),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let mut title = vec![game.quiz.current_quiz.unidad_tematica.clone().bold()];
+    if game.player.streak != StreakState::NoStreak {
+        title.push(format!("   Multiplicador x{}", game.player.streak.multiplier()).fg(Color::Yellow));
+    }
+    frame.render_widget(Line::from(title), header);
+
+    let [hero_bar, enemy_bar] = Layout::horizontal([Constraint::Fill(1); 2]).areas(bars);
+    frame.render_widget(health_gauge("Héroe", game.player.health.hero_health), hero_bar);
+    frame.render_widget(health_gauge("Enemigo", game.player.health.enemy_health), enemy_bar);
+
+    let center = match game.phase {
+        Phase::Rolling => {
+            // Igual que en la versión gráfica: el número parpadea y en el
+            // último segundo se muestra el que ya se tiró
+            let elapsed = game.dice.animation_start.map_or(Duration::ZERO, |start| start.elapsed());
+            let shown = if game.dice.duration.saturating_sub(elapsed) < Duration::from_secs(1) {
+                game.dice.rolled_number
+            } else {
+                rand::thread_rng().gen_range(1..=10)
+            };
+            format!("Lanzando el dado... {}", shown)
+        }
+        _ => {
+            let secs = game.quiz.remaining().as_secs();
+            format!("{:02}:{:02}", secs / 60, secs % 60)
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(center).bold().alignment(Alignment::Center).block(Block::bordered()),
+        timer,
+    );
+
+    frame.render_widget(
+        Paragraph::new(markup::to_plain(&game.quiz.current_quiz.pregunta, true))
+            .wrap(Wrap { trim: true })
+            .block(Block::bordered().title("Pregunta")),
+        question,
+    );
+
+    let mut options: Vec<(&String, &String)> = game.quiz.current_quiz.respuestas.iter().collect();
+    options.sort();
+    let options: Vec<Line> = options.into_iter()
+        .map(|(key, answer)| Line::from(format!("{}) {}", key, markup::to_plain(answer, true))))
+        .collect();
+    frame.render_widget(Paragraph::new(options).block(Block::bordered().title("Respuestas")), answers);
+
+    let footer_text = if game.message.is_empty() {
+        String::from("Presiona la letra de tu respuesta · Esc para salir")
+    } else {
+        format!("{} · Esc para salir", game.message)
+    };
+    frame.render_widget(Line::from(footer_text).italic(), footer);
+}
+
+fn health_gauge(title: &str, health: f32) -> Gauge<'_> {
+    Gauge::default()
+        .block(Block::bordered().title(title))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Red))
+        .ratio(health.clamp(0.0, 1.0) as f64)
+}
+
+fn draw_results(frame: &mut Frame, game: &Game) {
+    let data = &game.player.session_data;
+    let title = if game.player.is_alive() { "¡GANASTE!" } else { "PERDISTE" };
+    let hit_percentage = if data.total_quiz == 0 { 0.0 } else { data.get_hit_percentage() };
+    let lines = vec![
+        Line::from(title).bold(),
+        Line::from(""),
+        Line::from(format!("Respuestas correctas: {}", data.correct_answers)),
+        Line::from(format!("Respuestas incorrectas: {}", data.wrong_answers)),
+        Line::from(format!("Mejor racha: {}", data.win_streak.0)),
+        Line::from(format!("Mejor golpe: {}", data.best_hit)),
+        Line::from(format!("Porcentaje de aciertos: {:.0}%", hit_percentage)),
+        Line::from(""),
+        Line::from("Presiona cualquier tecla para salir").italic(),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).alignment(Alignment::Center).block(Block::bordered().title("Resultados")),
+        frame.area(),
+    );
+}
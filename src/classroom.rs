@@ -0,0 +1,525 @@
+use std::{collections::HashMap, io, net::{IpAddr, SocketAddr}, time::Duration};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+use crate::{app::QuizItem, net::{Client, ClientEvent, ClientId, Server, ServerEvent}};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum StudentMessage {
+  Join { name: String },
+  Answer { question_id: u32, key: String, elapsed_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum HostMessage {
+  Welcome { name: String },
+  Question { question_id: u32, item: Box<QuizItem>, duration_secs: u64 },
+  AnswerResult { question_id: u32, correct: bool, correct_key: String },
+  Standings { standings: Vec<Standing> },
+  Paused { question_id: u32 },
+  Resumed { question_id: u32, remaining_ms: u64 },
+  Reveal { question_id: u32, correct_key: String },
+  Skipped { question_id: u32 },
+  End,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Standing {
+  pub name: String,
+  pub correct: u32,
+  pub answered: u32,
+  pub total_time_ms: u64,
+  pub connected: bool,
+}
+
+impl Standing {
+  pub fn average_time_secs(&self) -> f32 {
+    if self.answered == 0 {
+      return 0.0;
+    }
+    self.total_time_ms as f32 / self.answered as f32 / 1000.0
+  }
+}
+
+fn sort_standings(standings: &mut [Standing]) {
+  standings.sort_by(|a, b| b.correct.cmp(&a.correct).then(a.total_time_ms.cmp(&b.total_time_ms)));
+}
+
+pub struct LanSettings {
+  pub port: u16,
+  pub address: String,
+  pub error: Option<String>,
+}
+
+impl Default for LanSettings {
+  fn default() -> Self {
+    LanSettings {
+      port: DEFAULT_PORT,
+      address: format!("127.0.0.1:{}", DEFAULT_PORT),
+      error: None,
+    }
+  }
+}
+
+pub enum Classroom {
+  Inactive,
+  Host(ClassroomHost),
+  Student(ClassroomStudent),
+}
+
+pub struct ActiveQuestion {
+  pub id: u32,
+  pub item: QuizItem,
+  pub started: Instant,
+  pub duration: Duration,
+  pub answers: HashMap<ClientId, (String, u64)>, // (clave elegida, ms)
+  pub paused_at: Option<Instant>,
+  pub paused_total: Duration,
+  pub revealed: bool,
+}
+
+impl ActiveQuestion {
+  // Tiempo corrido sin contar las pausas
+  pub fn elapsed(&self) -> Duration {
+    let now = self.paused_at.unwrap_or_else(Instant::now);
+    now.duration_since(self.started).saturating_sub(self.paused_total)
+  }
+
+  pub fn remaining(&self) -> Duration {
+    self.duration.saturating_sub(self.elapsed())
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+
+  pub fn is_open(&self) -> bool {
+    !self.revealed && !self.is_paused() && self.remaining() > Duration::ZERO
+  }
+
+  // (clave, texto, votos) para cada opción, en orden de clave
+  pub fn distribution(&self) -> Vec<(String, String, usize)> {
+    let mut distribution: Vec<(String, String, usize)> = self.item.respuestas.iter()
+      .map(|(key, text)| {
+        let votes = self.answers.values().filter(|(chosen, _)| chosen == key).count();
+        (key.clone(), text.clone(), votes)
+      })
+      .collect();
+    distribution.sort();
+    distribution
+  }
+}
+
+pub struct ClassroomHost {
+  server: Server<StudentMessage, HostMessage>,
+  pub students: Vec<(ClientId, Standing)>,
+  pub current: Option<ActiveQuestion>,
+  pub questions_sent: u32,
+  pub lan_ip: Option<IpAddr>,
+  bank: Vec<QuizItem>,
+  used: Vec<usize>,
+}
+
+impl ClassroomHost {
+  pub fn start(port: u16, bank: Vec<QuizItem>) -> io::Result<Self> {
+    Ok(ClassroomHost {
+      server: Server::bind(("0.0.0.0", port))?,
+      students: Vec::new(),
+      current: None,
+      questions_sent: 0,
+      lan_ip: crate::net::local_ip(),
+      bank,
+      used: Vec::new(),
+    })
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.server.local_addr()
+  }
+
+  pub fn update(&mut self) {
+    for event in self.server.poll() {
+      match event {
+        ServerEvent::Connected(_) => (),
+        ServerEvent::Message(id, StudentMessage::Join { name }) => self.join(id, name),
+        ServerEvent::Message(id, StudentMessage::Answer { question_id, key, elapsed_ms }) => {
+          self.answer(id, question_id, key, elapsed_ms);
+        }
+        ServerEvent::Disconnected(id) => {
+          if let Some((_, standing)) = self.students.iter_mut().find(|(student, _)| *student == id) {
+            standing.connected = false;
+            self.broadcast_standings();
+          }
+        }
+      }
+    }
+  }
+
+  fn join(&mut self, id: ClientId, name: String) {
+    if self.students.iter().any(|(student, _)| *student == id) {
+      return;
+    }
+    self.server.send_to(id, &HostMessage::Welcome { name: name.clone() });
+    self.students.push((id, Standing {
+      name,
+      connected: true,
+      ..Default::default()
+    }));
+
+    // Quien llega tarde recibe la pregunta en curso con el tiempo que le queda
+    if let Some(current) = self.current.as_ref().filter(|current| !current.revealed) {
+      self.server.send_to(id, &HostMessage::Question {
+        question_id: current.id,
+        item: Box::new(hide_answer(&current.item)),
+        duration_secs: current.remaining().as_secs(),
+      });
+      if current.is_paused() {
+        self.server.send_to(id, &HostMessage::Paused { question_id: current.id });
+      }
+    }
+    self.broadcast_standings();
+  }
+
+  fn answer(&mut self, id: ClientId, question_id: u32, key: String, elapsed_ms: u64) {
+    let Some(current) = self.current.as_mut() else { return };
+    if current.id != question_id || !current.is_open() || current.answers.contains_key(&id) {
+      return;
+    }
+    let Some((_, standing)) = self.students.iter_mut().find(|(student, _)| *student == id) else { return };
+
+    let correct = key == current.item.respuesta_correcta;
+    // El tiempo lo reporta el alumno, pero no puede ser mayor al que realmente pasó
+    let elapsed_ms = elapsed_ms.min(current.elapsed().as_millis() as u64);
+    standing.answered += 1;
+    standing.total_time_ms += elapsed_ms;
+    if correct {
+      standing.correct += 1;
+    }
+    current.answers.insert(id, (key, elapsed_ms));
+
+    self.server.send_to(id, &HostMessage::AnswerResult {
+      question_id,
+      correct,
+      correct_key: current.item.respuesta_correcta.clone(),
+    });
+    self.broadcast_standings();
+  }
+
+  pub fn next_question(&mut self) {
+    if self.bank.is_empty() {
+      return;
+    }
+    if self.used.len() >= self.bank.len() {
+      self.used.clear();
+    }
+    let available: Vec<usize> = (0..self.bank.len()).filter(|idx| !self.used.contains(idx)).collect();
+    let idx = available[rand::thread_rng().gen_range(0..available.len())];
+    self.used.push(idx);
+
+    let item = self.bank[idx].clone();
+    self.questions_sent += 1;
+    let question = ActiveQuestion {
+      id: self.questions_sent,
+      duration: item.time_limit(),
+      started: Instant::now(),
+      answers: HashMap::new(),
+      paused_at: None,
+      paused_total: Duration::ZERO,
+      revealed: false,
+      item,
+    };
+    self.server.broadcast(&HostMessage::Question {
+      question_id: question.id,
+      item: Box::new(hide_answer(&question.item)),
+      duration_secs: question.duration.as_secs(),
+    });
+    self.current = Some(question);
+  }
+
+  pub fn toggle_pause(&mut self) {
+    let Some(current) = self.current.as_mut().filter(|current| !current.revealed) else { return };
+    match current.paused_at.take() {
+      Some(paused_at) => {
+        current.paused_total += paused_at.elapsed();
+        self.server.broadcast(&HostMessage::Resumed {
+          question_id: current.id,
+          remaining_ms: current.remaining().as_millis() as u64,
+        });
+      }
+      None => {
+        current.paused_at = Some(Instant::now());
+        self.server.broadcast(&HostMessage::Paused { question_id: current.id });
+      }
+    }
+  }
+
+  // Cierra la pregunta y muestra la respuesta correcta a todos para comentarla
+  pub fn reveal(&mut self) {
+    let Some(current) = self.current.as_mut() else { return };
+    if let Some(paused_at) = current.paused_at.take() {
+      current.paused_total += paused_at.elapsed();
+    }
+    current.revealed = true;
+    self.server.broadcast(&HostMessage::Reveal {
+      question_id: current.id,
+      correct_key: current.item.respuesta_correcta.clone(),
+    });
+  }
+
+  // Descarta la pregunta actual: sus respuestas dejan de contar
+  pub fn skip(&mut self) {
+    let Some(current) = self.current.take() else { return };
+    for (id, (key, elapsed_ms)) in &current.answers {
+      if let Some((_, standing)) = self.students.iter_mut().find(|(student, _)| student == id) {
+        standing.answered -= 1;
+        standing.total_time_ms -= elapsed_ms;
+        if *key == current.item.respuesta_correcta {
+          standing.correct -= 1;
+        }
+      }
+    }
+    self.server.broadcast(&HostMessage::Skipped { question_id: current.id });
+    self.broadcast_standings();
+    self.next_question();
+  }
+
+  // Cada alumno con su respuesta a la pregunta en curso, si ya contestó
+  pub fn student_rows(&self) -> Vec<(&Standing, Option<&(String, u64)>)> {
+    self.students.iter()
+      .map(|(id, standing)| {
+        let answer = self.current.as_ref().and_then(|current| current.answers.get(id));
+        (standing, answer)
+      })
+      .collect()
+  }
+
+  pub fn standings(&self) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = self.students.iter().map(|(_, standing)| standing.clone()).collect();
+    sort_standings(&mut standings);
+    standings
+  }
+
+  fn broadcast_standings(&self) {
+    self.server.broadcast(&HostMessage::Standings { standings: self.standings() });
+  }
+
+  pub fn connected_students(&self) -> usize {
+    self.students.iter().filter(|(_, standing)| standing.connected).count()
+  }
+
+  pub fn end(&mut self) {
+    self.server.broadcast(&HostMessage::End);
+  }
+}
+
+// Los alumnos no reciben la clave correcta hasta que contestan
+fn hide_answer(item: &QuizItem) -> QuizItem {
+  let mut item = item.clone();
+  item.respuesta_correcta.clear();
+  item
+}
+
+pub struct StudentQuestion {
+  pub id: u32,
+  pub item: QuizItem,
+  pub received: Instant,
+  pub duration: Duration,
+  pub paused_at: Option<Instant>,
+  pub answer_time: Duration, // tiempo de respuesta acumulado antes de la última reanudación
+}
+
+impl StudentQuestion {
+  pub fn remaining(&self) -> Duration {
+    let now = self.paused_at.unwrap_or_else(Instant::now);
+    self.duration.saturating_sub(now.duration_since(self.received))
+  }
+
+  pub fn is_paused(&self) -> bool {
+    self.paused_at.is_some()
+  }
+
+  fn elapsed(&self) -> Duration {
+    self.answer_time + self.received.elapsed()
+  }
+}
+
+pub struct ClassroomStudent {
+  client: Client<HostMessage, StudentMessage>,
+  pub name: String,
+  pub current: Option<StudentQuestion>,
+  pub chosen: Option<String>,
+  pub result: Option<(bool, String)>, // (correcta, clave correcta)
+  pub standings: Vec<Standing>,
+  pub connected: bool,
+  pub finished: bool,
+}
+
+impl ClassroomStudent {
+  pub fn join(address: &str, name: &str) -> io::Result<Self> {
+    let mut client = Client::connect(address)?;
+    client.send(&StudentMessage::Join { name: name.to_owned() })?;
+    Ok(ClassroomStudent {
+      client,
+      name: name.to_owned(),
+      current: None,
+      chosen: None,
+      result: None,
+      standings: Vec::new(),
+      connected: true,
+      finished: false,
+    })
+  }
+
+  pub fn update(&mut self) {
+    for event in self.client.poll() {
+      match event {
+        ClientEvent::Message(HostMessage::Welcome { name }) => self.name = name,
+        ClientEvent::Message(HostMessage::Question { question_id, item, duration_secs }) => {
+          self.current = Some(StudentQuestion {
+            id: question_id,
+            item: *item,
+            received: Instant::now(),
+            duration: Duration::from_secs(duration_secs),
+            paused_at: None,
+            answer_time: Duration::ZERO,
+          });
+          self.chosen = None;
+          self.result = None;
+        }
+        ClientEvent::Message(HostMessage::AnswerResult { question_id, correct, correct_key }) => {
+          if self.current.as_ref().is_some_and(|current| current.id == question_id) {
+            self.result = Some((correct, correct_key));
+          }
+        }
+        ClientEvent::Message(HostMessage::Standings { standings }) => self.standings = standings,
+        ClientEvent::Message(HostMessage::Paused { question_id }) => {
+          if let Some(current) = self.current.as_mut().filter(|current| current.id == question_id) {
+            current.paused_at = Some(Instant::now());
+          }
+        }
+        ClientEvent::Message(HostMessage::Resumed { question_id, remaining_ms }) => {
+          if let Some(current) = self.current.as_mut().filter(|current| current.id == question_id) {
+            // El profesor manda el tiempo restante para que todos queden sincronizados
+            if let Some(paused_at) = current.paused_at.take() {
+              current.answer_time += paused_at.duration_since(current.received);
+            }
+            current.received = Instant::now();
+            current.duration = Duration::from_millis(remaining_ms);
+          }
+        }
+        ClientEvent::Message(HostMessage::Reveal { question_id, correct_key }) => {
+          if let Some(current) = self.current.as_mut().filter(|current| current.id == question_id) {
+            current.duration = Duration::ZERO;
+            let correct = self.chosen.as_ref() == Some(&correct_key);
+            self.result = Some((correct, correct_key));
+          }
+        }
+        ClientEvent::Message(HostMessage::Skipped { question_id }) => {
+          if self.current.as_ref().is_some_and(|current| current.id == question_id) {
+            self.current = None;
+            self.chosen = None;
+            self.result = None;
+          }
+        }
+        ClientEvent::Message(HostMessage::End) => self.finished = true,
+        ClientEvent::Disconnected => self.connected = false,
+      }
+    }
+  }
+
+  pub fn answer(&mut self, key: &str) {
+    let Some(current) = &self.current else { return };
+    if self.chosen.is_some() || current.is_paused() || current.remaining() == Duration::ZERO {
+      return;
+    }
+    let msg = StudentMessage::Answer {
+      question_id: current.id,
+      key: key.to_owned(),
+      elapsed_ms: current.elapsed().as_millis() as u64,
+    };
+    if self.client.send(&msg).is_err() {
+      self.connected = false;
+    }
+    self.chosen = Some(key.to_owned());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{quiz_item, wait_for};
+
+  fn bank() -> Vec<QuizItem> {
+    vec![quiz_item("1 - Prueba", "¿2 + 2?", &[("a", "4"), ("b", "5")], "a")]
+  }
+
+  fn connect(host: &mut ClassroomHost, name: &str) -> ClassroomStudent {
+    let address = format!("127.0.0.1:{}", host.local_addr().port());
+    let mut student = ClassroomStudent::join(&address, name).unwrap();
+    let joined = host.students.len() + 1;
+    wait_for(|| {
+      host.update();
+      student.update();
+      host.students.len() == joined && !student.standings.is_empty()
+    });
+    student
+  }
+
+  #[test]
+  fn student_joins_and_answers() {
+    let mut host = ClassroomHost::start(0, bank()).unwrap();
+    let mut student = connect(&mut host, "Ana");
+    assert_eq!(host.students[0].1.name, "Ana");
+
+    host.next_question();
+    wait_for(|| {
+      student.update();
+      student.current.is_some()
+    });
+    // La clave correcta no viaja con la pregunta
+    assert!(student.current.as_ref().unwrap().item.respuesta_correcta.is_empty());
+
+    student.answer("a");
+    wait_for(|| {
+      host.update();
+      student.update();
+      student.result.is_some()
+    });
+    assert_eq!(student.result, Some((true, String::from("a"))));
+    let standing = &host.standings()[0];
+    assert_eq!((standing.answered, standing.correct), (1, 1));
+  }
+
+  #[test]
+  fn late_student_gets_current_question_and_skip_undoes_answers() {
+    let mut host = ClassroomHost::start(0, bank()).unwrap();
+    let mut first = connect(&mut host, "Ana");
+    host.next_question();
+    wait_for(|| {
+      first.update();
+      first.current.is_some()
+    });
+    first.answer("b");
+    wait_for(|| {
+      host.update();
+      first.update();
+      first.result.is_some()
+    });
+    assert_eq!(first.result, Some((false, String::from("a"))));
+
+    let mut late = connect(&mut host, "Beto");
+    wait_for(|| {
+      late.update();
+      late.current.is_some()
+    });
+    assert_eq!(late.current.as_ref().unwrap().id, host.current.as_ref().unwrap().id);
+
+    host.skip();
+    assert!(host.standings().iter().all(|standing| standing.answered == 0));
+  }
+}
@@ -1,10 +1,17 @@
-use std::{thread, time::{Duration, Instant}};
+use std::time::Duration;
 
 use egui::{Color32, RichText, Ui};
 use rand::Rng;
-use crate::app::{App, StreakState};
+use crate::{app::{AnswerFeedback, App, GameMode, QuizItem, StreakState}, assets, markup::{self, Block}, classroom::{ActiveQuestion, ClassroomHost, ClassroomStudent, Standing}, duel::{DuelSnapshot, DuelStatus}, exam::ExamAnswer};
 
 const WHITE: Color32 = egui::Color32::WHITE;
+const QUESTION_IMAGE_HEIGHT: f32 = 180.0;
+const ANSWER_IMAGE_HEIGHT: f32 = 70.0;
+// Duelo local: el jugador 1 contesta con 1-4 y el jugador 2 con 7-0
+const DUEL_KEYS: [[egui::Key; 4]; 2] = [
+  [egui::Key::Num1, egui::Key::Num2, egui::Key::Num3, egui::Key::Num4],
+  [egui::Key::Num7, egui::Key::Num8, egui::Key::Num9, egui::Key::Num0],
+];
 
 pub fn health_bar(ui: &mut Ui, health: f32, right_to_left: bool) {
   let (_, rect) = ui.allocate_space(egui::vec2(200.0, 25.0));
@@ -38,8 +45,74 @@ pub fn health_bar(ui: &mut Ui, health: f32, right_to_left: bool) {
   );
 }
 
+// Texto de preguntas y respuestas con formato (negritas, listas, fórmulas
+// entre $...$) en una sola etiqueta. Sin color se usa el del botón o la etiqueta.
+pub fn quiz_text(text: &str, size: f32, color: Option<Color32>) -> egui::WidgetText {
+  markup::layout_job(text, size, color.unwrap_or(Color32::PLACEHOLDER)).into()
+}
+
+// Como `quiz_text`, pero las tablas se dibujan como cuadrícula. Va centrado
+// de arriba hacia abajo aunque el layout de afuera sea bottom_up.
+pub fn quiz_block(ui: &mut Ui, text: &str, size: f32, color: Option<Color32>) {
+  let color = color.unwrap_or(Color32::PLACEHOLDER);
+  ui.vertical_centered(|ui| {
+    for (idx, block) in markup::blocks(text).into_iter().enumerate() {
+      match block {
+        Block::Paragraph(paragraph) => {
+          ui.label(markup::layout_job(&paragraph, size, color));
+        }
+        Block::List(items) => {
+          for item in items {
+            ui.label(markup::layout_job(&format!("• {}", item), size, color));
+          }
+        }
+        Block::Table { header, rows } => {
+          let size = size.min(18.0);
+          egui::Grid::new(ui.id().with(("tabla", idx))).striped(true).show(ui, |ui| {
+            for (row_idx, row) in rows.iter().enumerate() {
+              for cell in row {
+                let mut spans = markup::inline(cell);
+                if header && row_idx == 0 {
+                  spans.iter_mut().for_each(|styled| styled.bold = true);
+                }
+                ui.label(markup::job(&spans, size, color));
+              }
+              ui.end_row();
+            }
+          });
+        }
+      }
+    }
+  });
+}
+
+// Imagen de la pregunta, si tiene, debajo del texto
+pub fn question_image(ui: &mut Ui, item: &QuizItem) {
+  let Some(path) = &item.imagen else { return };
+  match assets::bank_image(path) {
+    Some(source) => {
+      ui.add(egui::Image::new(source).max_height(QUESTION_IMAGE_HEIGHT).max_width(ui.available_width()));
+    }
+    None => {
+      ui.label(RichText::new(format!("(imagen no encontrada: {})", path)).size(13.0).color(Color32::GRAY));
+    }
+  }
+}
+
+// Botón de una respuesta y el tamaño con que se dibuja; si la opción tiene
+// imagen, el botón crece para que quepa junto al texto
+pub fn answer_button(item: &QuizItem, key: &str, text: impl Into<egui::WidgetText>) -> (egui::Button<'static>, egui::Vec2) {
+  let size = egui::vec2(250.0, 45.0);
+  match item.imagenes_respuestas.get(key).and_then(|path| assets::bank_image(path)) {
+    Some(source) => (
+      egui::Button::image_and_text(egui::Image::new(source).max_height(ANSWER_IMAGE_HEIGHT), text),
+      size + egui::vec2(0.0, ANSWER_IMAGE_HEIGHT),
+    ),
+    None => (egui::Button::new(text), size),
+  }
+}
+
 pub fn question_mode_1(ui: &mut Ui, app: &mut App) {
-  let button_size = egui::vec2(250.0, 45.0);
   let num_of_answers = app.quiz.current_quiz.respuestas.len() as f32;
   let spacing = if num_of_answers == 2.0 {
     51.0
@@ -52,43 +125,49 @@ pub fn question_mode_1(ui: &mut Ui, app: &mut App) {
   ui.vertical_centered(|ui| {
     ui.add_space(spacing);
     for (key, answer) in answers.iter() {
+      let (button, button_size) = answer_button(&app.quiz.current_quiz, key, quiz_text(answer, 15.0, None));
+      // En el examen no se revela la respuesta correcta hasta el reporte final
+      if app.mode == GameMode::Examen {
+        if ui.add_sized(button_size, button).clicked() {
+          record_exam_answer(app, key);
+        }
+        ui.add_space(spacing);
+        continue;
+      }
       if correct_ans == key {
         let clicked = ui.add_enabled_ui(!app.rnd_animation.is_animating, |ui| {
           ui.add_sized(
             button_size,
-            egui::Button::new(RichText::new(answer).size(15.0))
-              .fill(Color32::DARK_GREEN)
+            button.fill(Color32::DARK_GREEN)
           ).clicked()
         }).inner;
         if clicked {
-          app.session_data.total_quiz += 1;
-          app.session_data.correct_answers += 1;
-
-          let (mut best_streak, mut current_streak) = app.session_data.win_streak;
-          current_streak += 1;
-          if current_streak >= best_streak {
-            best_streak = current_streak;
+          app.log_answer(Some(key));
+          if app.mode == GameMode::Practica {
+            app.player_mut().session_data.record_answer(true);
+            show_feedback(app, true);
+            continue;
           }
-
-          app.session_data.win_streak.0 = best_streak;
-          app.session_data.win_streak.1 = current_streak;
-          app.rnd_animation.is_animating = true;
-          app.rnd_animation.animation_start = Some(Instant::now());
+          app.player_mut().resolve_answer(true);
+          app.rnd_animation.start();
         }
       }else {
         let clicked = ui.add_enabled_ui(!app.rnd_animation.is_animating, |ui| {
           ui.add_sized(
             button_size,
-            egui::Button::new(RichText::new(answer).size(15.0))
+            button
           ).clicked()
         }).inner;
         if clicked {
-          app.streak = StreakState::NoStreak;
-          app.health.hero_health -= 0.1;
-          app.health.hero_health = app.health.hero_health.clamp(0.0, 1.0);
-          app.session_data.total_quiz += 1;
-          app.session_data.wrong_answers += 1;
-          app.session_data.win_streak.1 = 0;
+          if app.mode == GameMode::Practica {
+            app.player_mut().session_data.record_answer(false);
+            app.log_answer(Some(key));
+            app.practice.missed.push(app.quiz.current_quiz.clone());
+            show_feedback(app, false);
+            continue;
+          }
+          app.player_mut().resolve_answer(false);
+          app.log_answer(Some(key));
           select_new_quiz(app);
         }
       }
@@ -97,38 +176,134 @@ pub fn question_mode_1(ui: &mut Ui, app: &mut App) {
   });
 }
 
+fn record_exam_answer(app: &mut App, key: &str) {
+  app.log_answer(Some(key));
+  app.exam.answers.push(ExamAnswer {
+    item: app.quiz.current_quiz.clone(),
+    chosen: Some(key.to_owned()),
+  });
+  let correct = key == app.quiz.current_quiz.respuesta_correcta;
+  app.player_mut().session_data.record_answer(correct);
+
+  match app.quiz.quiz_items.get(app.exam.answers.len()) {
+    Some(next) => {
+      app.quiz.current_quiz = next.clone();
+      app.quiz.asked_at = web_time::Instant::now();
+    }
+    None => finish_exam(app),
+  }
+}
+
+fn finish_exam(app: &mut App) {
+  // Las preguntas que no alcanzaron a contestarse cuentan como erróneas
+  let unanswered: Vec<QuizItem> = app.quiz.quiz_items.iter().skip(app.exam.answers.len()).cloned().collect();
+  for item in unanswered {
+    let record = app.answer_record(&item, None, Duration::ZERO);
+    app.answer_log.push(record);
+    app.player_mut().session_data.record_answer(false);
+    app.exam.answers.push(ExamAnswer {
+      item,
+      chosen: None,
+    });
+  }
+  app.exam.time_used = app.quiz.start_time.elapsed().min(app.quiz.duration);
+  app.finish_run();
+}
+
+pub fn exam_timer(ui: &mut Ui, app: &mut App, remaining: Duration) {
+  ui.vertical_centered(|ui| {
+    let minutes = remaining.as_secs() / 60;
+    let seconds = remaining.as_secs() % 60;
+
+    ui.heading(egui::RichText::new(format!("{:02}:{:02}", minutes, seconds))
+      .size(60.)
+      .color(WHITE)
+    );
+    ui.label(RichText::new(format!(
+      "Pregunta {} de {}",
+      (app.exam.answers.len() + 1).min(app.quiz.quiz_items.len()),
+      app.quiz.quiz_items.len()
+    )).size(15.0));
+    if remaining == Duration::from_secs(0) || ui.button("Entregar examen").clicked() {
+      finish_exam(app);
+    }
+  });
+}
+
+fn show_feedback(app: &mut App, correct: bool) {
+  let quiz = &app.quiz.current_quiz;
+  app.feedback = Some(AnswerFeedback {
+    correct,
+    correct_answer: quiz.respuestas.get(&quiz.respuesta_correcta).cloned().unwrap_or_default(),
+    explicacion: quiz.explicacion.clone(),
+  });
+}
+
+pub fn answer_feedback(ui: &mut Ui, app: &mut App) {
+  let Some(feedback) = app.feedback.clone() else { return };
+
+  ui.vertical_centered(|ui| {
+    ui.add_space(15.0);
+    if feedback.correct {
+      ui.label(RichText::new("¡Correcto!").size(25.0).color(Color32::GREEN));
+    } else {
+      ui.label(RichText::new("Incorrecto").size(25.0).color(Color32::RED));
+    }
+    ui.add_space(10.0);
+    ui.label(quiz_text(&format!("Respuesta correcta: {}", feedback.correct_answer), 18.0, Some(WHITE)));
+    ui.add_space(10.0);
+    if let Some(explicacion) = &feedback.explicacion {
+      quiz_block(ui, explicacion, 15.0, None);
+    }
+    ui.add_space(15.0);
+
+    if ui.add_sized(egui::vec2(250.0, 45.0), egui::Button::new(RichText::new("Siguiente").size(15.0))).clicked() {
+      app.feedback = None;
+      if app.player().session_data.total_quiz as usize >= app.quiz.quiz_items.len() {
+        app.finish_run();
+      } else {
+        select_new_quiz(app);
+      }
+    }
+  });
+}
+
+pub fn practice_progress(ui: &mut Ui, app: &mut App) {
+  ui.vertical_centered(|ui| {
+    ui.heading(egui::RichText::new(format!(
+      "Pregunta {} de {}",
+      (app.player().session_data.total_quiz as usize + 1).min(app.quiz.quiz_items.len()),
+      app.quiz.quiz_items.len()
+    ))
+      .size(30.)
+      .color(WHITE)
+    );
+    if ui.button("Terminar práctica").clicked() {
+      app.feedback = None;
+      app.finish_run();
+    }
+  });
+}
+
 pub fn rand_num_animation(ui: &mut Ui, app: &mut App, ctx: &egui::Context) {
   let remaining = if app.rnd_animation.animation_start.unwrap().elapsed() >= app.rnd_animation.duration {
     Duration::from_secs(0)
   } else {
     app.rnd_animation.duration - app.rnd_animation.animation_start.unwrap().elapsed()
   };
+
+  if remaining < Duration::from_secs(1) {
+    app.rnd_animation.rnd_number = app.rnd_animation.rolled_number;
+  }
   
   if remaining == Duration::from_secs(0) {
-    match app.streak {
-      StreakState::NoStreak => {
-        app.health.enemy_health -= app.rnd_animation.rnd_number as f32/100.0;
-      },
-      StreakState::X2 => {
-        app.health.enemy_health -= (app.rnd_animation.rnd_number as f32/100.0)*2.0;
-        app.rnd_animation.rnd_number = app.rnd_animation.rnd_number * 2 
-      },
-      StreakState::X3 => {
-        app.health.enemy_health -= (app.rnd_animation.rnd_number as f32/100.0)*3.0;
-        app.rnd_animation.rnd_number = app.rnd_animation.rnd_number * 3 
-      }
-    }
-    match app.session_data.win_streak.1 {
-      streak if streak >= 3 && streak < 5 => app.streak = StreakState::X2,
-      streak if streak >= 5 => app.streak = StreakState::X3,
-      _ => ()
-    }
-    app.health.enemy_health = app.health.enemy_health.clamp(0.0, 1.0);
+    let rolled = app.rnd_animation.rolled_number;
+    let damage = app.player_mut().resolve_roll(rolled);
+    app.damage_target(damage as f32 / 100.0);
+    app.rnd_animation.rnd_number = damage;
+    app.log_damage(damage as u32);
     app.rnd_animation.is_animating = false;
     app.rnd_animation.animation_start = None;
-    if app.rnd_animation.rnd_number as u8 >= app.session_data.best_hit {
-      app.session_data.best_hit = app.rnd_animation.rnd_number as u8;
-    }
     select_new_quiz(app);
   }
 
@@ -148,7 +323,7 @@ pub fn rand_num_animation(ui: &mut Ui, app: &mut App, ctx: &egui::Context) {
     }
 
     if remaining <= Duration::from_secs(1) {
-      match app.streak {
+      match app.player().streak {
         StreakState::NoStreak => {
           ui.label(egui::RichText::new(app.rnd_animation.rnd_number.to_string())
             .size(30.)
@@ -193,12 +368,15 @@ pub fn rand_num_animation(ui: &mut Ui, app: &mut App, ctx: &egui::Context) {
     }
   });
 
+  // Solo es efecto visual; el valor real ya se tiró con el rng de la partida
   if remaining >= Duration::from_secs(1) {
     let mut rng = rand::thread_rng();
     app.rnd_animation.rnd_number = rng.gen_range(1..=10);
   }
 
-  thread::sleep(Duration::from_millis(50));
+  // En el navegador no se puede dormir el hilo; ahí basta con pedir otro cuadro
+  #[cfg(not(target_arch = "wasm32"))]
+  std::thread::sleep(Duration::from_millis(50));
   ctx.request_repaint();
 }
 
@@ -208,8 +386,8 @@ pub fn timer(ui: &mut Ui, app: &mut App, remaining: Duration) {
     let seconds = remaining.as_secs() % 60;
 
     if remaining == Duration::from_secs(0) {
-      app.health.hero_health -= 0.1;
-      app.health.hero_health = app.health.hero_health.clamp(0.0, 1.0);
+      app.player_mut().resolve_timeout();
+      app.log_answer(None);
       select_new_quiz(app);
     }
 
@@ -220,40 +398,258 @@ pub fn timer(ui: &mut Ui, app: &mut App, remaining: Duration) {
   });
 }
 
+// Vista previa del editor: la pregunta como se ve en la partida, con el
+// temporizador detenido y botones que no responden
+pub fn question_preview(ui: &mut Ui, item: &QuizItem) {
+  ui.label(RichText::new(&item.unidad_tematica).size(15.0));
+  ui.separator();
+  ui.vertical_centered(|ui| {
+    let secs = item.time_limit().as_secs();
+    ui.heading(RichText::new(format!("{:02}:{:02}", secs / 60, secs % 60))
+      .size(60.)
+      .color(WHITE)
+    );
+    quiz_block(ui, &item.pregunta, 30., Some(WHITE));
+    question_image(ui, item);
+    ui.add_space(12.0);
+
+    let mut answers: Vec<(&String, &String)> = item.respuestas.iter().collect();
+    answers.sort();
+    for (key, answer) in answers {
+      let (mut button, button_size) = answer_button(item, key, quiz_text(answer, 15.0, None));
+      button = button.sense(egui::Sense::hover());
+      if *key == item.respuesta_correcta {
+        button = button.fill(Color32::DARK_GREEN);
+      }
+      ui.add_sized(button_size, button);
+      ui.add_space(12.0);
+    }
+  });
+}
+
 pub fn select_new_quiz(app: &mut App) {
-  let new_quiz = get_unused_quiz_index(app).unwrap_or(0);
-  app.quiz.current_quiz = app.quiz.quiz_items.get(new_quiz)
-    .unwrap()
-    .to_owned();
-
-  app.quiz.used_quiz_items[app.quiz.used_quiz_idx] = new_quiz as u8;
-  app.quiz.used_quiz_idx += 1;
-
-  app.quiz.duration = match app.quiz.current_quiz.tipo_reactivo.as_str() {
-    "Opción Múltiple" => Duration::from_secs(31),
-    "Verdadero o Falso" => Duration::from_secs(16),
-    "Ejercicio" => Duration::from_secs(300),
-    _ => Duration::from_secs(0)
-  };
-  app.quiz.start_time = Instant::now();
+  app.quiz.next_question();
+  app.next_turn();
+}
+
+pub fn standings_table(ui: &mut Ui, standings: &[Standing]) {
+  ui.add_space(10.0);
+  ui.vertical_centered(|ui| {
+    ui.label(RichText::new("Posiciones").size(25.0).color(WHITE));
+  });
+  ui.add_space(10.0);
+  egui::ScrollArea::vertical().show(ui, |ui| {
+    egui::Grid::new("standings_table")
+      .striped(true)
+      .spacing([20.0, 8.0])
+      .show(ui, |ui| {
+        ui.label(RichText::new("#").size(15.0));
+        ui.label(RichText::new("Alumno").size(15.0));
+        ui.label(RichText::new("Aciertos").size(15.0));
+        ui.label(RichText::new("Tiempo prom.").size(15.0));
+        ui.end_row();
+        for (idx, standing) in standings.iter().enumerate() {
+          let color = if standing.connected { WHITE } else { Color32::DARK_GRAY };
+          ui.label(RichText::new((idx + 1).to_string()).size(15.0).color(color));
+          ui.label(RichText::new(&standing.name).size(15.0).color(color));
+          ui.label(RichText::new(format!("{} / {}", standing.correct, standing.answered)).size(15.0).color(color));
+          ui.label(RichText::new(format!("{:.1} s", standing.average_time_secs())).size(15.0).color(color));
+          ui.end_row();
+        }
+      });
+  });
+}
+
+pub fn student_dashboard(ui: &mut Ui, host: &ClassroomHost) {
+  ui.add_space(10.0);
+  ui.vertical_centered(|ui| {
+    ui.label(RichText::new("Alumnos").size(25.0).color(WHITE));
+  });
+  ui.add_space(10.0);
+
+  let mut rows = host.student_rows();
+  rows.sort_by(|(a, _), (b, _)| b.correct.cmp(&a.correct).then(a.total_time_ms.cmp(&b.total_time_ms)));
+  let correct_key = host.current.as_ref().map(|current| current.item.respuesta_correcta.as_str());
+
+  egui::ScrollArea::vertical().show(ui, |ui| {
+    egui::Grid::new("student_dashboard_table")
+      .striped(true)
+      .spacing([15.0, 8.0])
+      .show(ui, |ui| {
+        ui.label(RichText::new("Alumno").size(14.0));
+        ui.label(RichText::new("Aciertos").size(14.0));
+        ui.label(RichText::new("Precisión").size(14.0));
+        ui.label(RichText::new("Tiempo prom.").size(14.0));
+        ui.label(RichText::new("Esta pregunta").size(14.0));
+        ui.end_row();
+        for (standing, answer) in rows {
+          let color = if standing.connected { WHITE } else { Color32::DARK_GRAY };
+          let accuracy = if standing.answered == 0 {
+            0.0
+          } else {
+            standing.correct as f32 / standing.answered as f32 * 100.0
+          };
+          ui.label(RichText::new(&standing.name).size(14.0).color(color));
+          ui.label(RichText::new(format!("{} / {}", standing.correct, standing.answered)).size(14.0).color(color));
+          ui.label(RichText::new(format!("{:.0}%", accuracy)).size(14.0).color(color));
+          ui.label(RichText::new(format!("{:.1} s", standing.average_time_secs())).size(14.0).color(color));
+          match answer {
+            Some((key, elapsed_ms)) => {
+              let answer_color = if Some(key.as_str()) == correct_key { Color32::GREEN } else { Color32::RED };
+              ui.label(RichText::new(format!("{} ({:.1} s)", key, *elapsed_ms as f32 / 1000.0)).size(14.0).color(answer_color));
+            }
+            None => {
+              ui.label(RichText::new("—").size(14.0).color(color));
+            }
+          }
+          ui.end_row();
+        }
+      });
+  });
+}
+
+pub fn answer_distribution(ui: &mut Ui, question: &ActiveQuestion) {
+  let distribution = question.distribution();
+  let total = question.answers.len().max(1) as f32;
+
+  for (key, text, votes) in distribution {
+    ui.horizontal(|ui| {
+      let is_correct = key == question.item.respuesta_correcta;
+      let label_color = if is_correct { Color32::GREEN } else { WHITE };
+      ui.add_sized(
+        egui::vec2(220.0, 20.0),
+        egui::Label::new(RichText::new(format!("{}) {}", key, text)).size(14.0).color(label_color)).truncate()
+      );
+
+      let (_, rect) = ui.allocate_space(egui::vec2(150.0, 20.0));
+      ui.painter().rect_filled(rect, 2.5, Color32::from_gray(60));
+      let fill = egui::Rect::from_min_size(rect.min, egui::vec2(rect.width() * votes as f32 / total, rect.height()));
+      let bar_color = if is_correct { Color32::from_rgb(62, 148, 37) } else { Color32::from_rgb(70, 110, 180) };
+      ui.painter().rect_filled(fill, 2.5, bar_color);
+
+      ui.label(RichText::new(votes.to_string()).size(14.0));
+    });
+  }
+}
+
+pub fn raid_answers(ui: &mut Ui, app: &mut App) {
+  let spacing = if app.quiz.current_quiz.respuestas.len() == 2 { 51.0 } else { 12.0 };
+  let correct_key = app.quiz.current_quiz.respuesta_correcta.clone();
+  let answers = app.quiz.current_quiz.respuestas.clone();
+  let mut answered = None;
+
+  ui.vertical_centered(|ui| {
+    ui.add_space(spacing);
+    for (key, answer) in answers.iter() {
+      let (button, button_size) = answer_button(&app.quiz.current_quiz, key, quiz_text(answer, 15.0, None));
+      if ui.add_sized(button_size, button).clicked() {
+        answered = Some(*key == correct_key);
+      }
+      ui.add_space(spacing);
+    }
+  });
 
-  if app.quiz.used_quiz_idx >= app.quiz.used_quiz_items.len() {
-    app.quiz.used_quiz_items = [0; 40];
-    app.quiz.used_quiz_idx = 0;
+  if let Some(correct) = answered {
+    app.raid.answer(correct);
+    select_new_quiz(app);
   }
 }
 
-fn get_unused_quiz_index(app: &App) -> Option<usize> {
-  let available_indices: Vec<usize> = (0..app.quiz.quiz_items.len())
-    .filter(|&index| !app.quiz.used_quiz_items.contains(&(index as u8)))
-    .collect();
+pub fn lan_answers(ui: &mut Ui, student: &mut ClassroomStudent) {
+  let Some(current) = &student.current else { return };
+  if student.finished {
+    return;
+  }
+  let spacing = if current.item.respuestas.len() == 2 { 51.0 } else { 12.0 };
+  let can_answer = student.chosen.is_none() && !current.is_paused() && current.remaining() > Duration::ZERO;
+
+  let mut answers: Vec<(String, String)> = current.item.respuestas.clone().into_iter().collect();
+  answers.sort();
+  let mut clicked_key = None;
 
-  if available_indices.is_empty() {
-    return None;
+  ui.vertical_centered(|ui| {
+    ui.add_space(spacing);
+    for (key, answer) in answers.iter() {
+      let (mut button, button_size) = answer_button(&current.item, key, quiz_text(answer, 15.0, None));
+      if let Some((_, correct_key)) = &student.result {
+        if key == correct_key {
+          button = button.fill(Color32::DARK_GREEN);
+        } else if student.chosen.as_ref() == Some(key) {
+          button = button.fill(Color32::from_rgb(163, 43, 38));
+        }
+      } else if student.chosen.as_ref() == Some(key) {
+        button = button.fill(Color32::DARK_BLUE);
+      }
+      let clicked = ui.add_enabled_ui(can_answer, |ui| {
+        ui.add_sized(button_size, button).clicked()
+      }).inner;
+      if clicked {
+        clicked_key = Some(key.clone());
+      }
+      ui.add_space(spacing);
+    }
+  });
+
+  if let Some(key) = clicked_key {
+    student.answer(&key);
   }
+}
 
-  let mut rng = rand::thread_rng();
-  let random_index = available_indices[rng.gen_range(0..available_indices.len())];
-  
-  Some(random_index)
-}
\ No newline at end of file
+pub fn duel_answers(ui: &mut Ui, app: &mut App, snapshot: &DuelSnapshot) {
+  let Some(question) = &snapshot.question else { return };
+  let spacing = if question.respuestas.len() == 2 { 51.0 } else { 12.0 };
+  let local_side = app.duel.local_side();
+  let open = snapshot.status == DuelStatus::Question && app.duel.is_connected();
+
+  let mut answers: Vec<(String, String)> = question.respuestas.clone().into_iter().collect();
+  answers.sort();
+  let mut chosen: Vec<(usize, String)> = Vec::new();
+
+  if local_side.is_none() && open {
+    ui.input(|input| {
+      for (side, keys) in DUEL_KEYS.iter().enumerate() {
+        for (key, (answer_key, _)) in keys.iter().zip(answers.iter()) {
+          if input.key_pressed(*key) {
+            chosen.push((side, answer_key.clone()));
+          }
+        }
+      }
+    });
+  }
+
+  ui.vertical_centered(|ui| {
+    ui.add_space(spacing);
+    for (idx, (key, answer)) in answers.iter().enumerate() {
+      let text = match local_side {
+        Some(_) => answer.clone(),
+        None => format!("[{} | {}]  {}", idx + 1, (idx + 7) % 10, answer),
+      };
+      let (mut button, button_size) = answer_button(question, key, quiz_text(&text, 15.0, None));
+      if let Some(result) = &snapshot.result {
+        if *key == result.correct_key {
+          button = button.fill(Color32::DARK_GREEN);
+        } else if result.answers.iter().flatten().any(|(chosen_key, _)| chosen_key == key) {
+          button = button.fill(Color32::from_rgb(163, 43, 38));
+        }
+      }
+      match local_side {
+        Some(side) => {
+          let clicked = ui.add_enabled_ui(open && !snapshot.answered[side], |ui| {
+            ui.add_sized(button_size, button).clicked()
+          }).inner;
+          if clicked {
+            chosen.push((side, key.clone()));
+          }
+        }
+        None => {
+          ui.add_sized(button_size, button.sense(egui::Sense::hover()));
+        }
+      }
+      ui.add_space(spacing);
+    }
+  });
+
+  for (side, key) in chosen {
+    app.duel.answer(side, &key);
+  }
+}
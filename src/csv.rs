@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use crate::{app::{QuizItem, ITEM_TYPES}, import::{ImportReport, Skipped}};
+
+// Banco en CSV para mantenerlo en una hoja de cálculo: una fila por pregunta
+// y una columna por cada clave de respuesta ("Respuesta a", "Respuesta V"...).
+// Una celda vacía significa que la pregunta no tiene esa opción. Las columnas
+// de imágenes ("Imagen", "Imagen a"...) solo se escriben si el banco las usa.
+
+const UNIT: &str = "Unidad Temática";
+const QUESTION: &str = "Pregunta";
+const TYPE: &str = "Tipo de reactivo";
+const CORRECT: &str = "Respuesta correcta";
+const DIFFICULTY: &str = "Dificultad";
+const EXPLANATION: &str = "Explicación";
+const ANSWER_PREFIX: &str = "Respuesta ";
+const IMAGE: &str = "Imagen";
+const IMAGE_PREFIX: &str = "Imagen ";
+
+// Excel solo reconoce el CSV como UTF-8 si empieza con BOM
+pub(crate) const BOM: char = '\u{feff}';
+
+pub fn write(items: &[QuizItem]) -> String {
+  let mut keys: Vec<&String> = items.iter().flat_map(|item| item.respuestas.keys()).collect();
+  keys.sort();
+  keys.dedup();
+
+  let mut image_keys: Vec<&String> = items.iter().flat_map(|item| item.imagenes_respuestas.keys()).collect();
+  image_keys.sort();
+  image_keys.dedup();
+  let has_images = !image_keys.is_empty() || items.iter().any(|item| item.imagen.is_some());
+
+  let mut header: Vec<String> = [UNIT, QUESTION, TYPE, CORRECT, DIFFICULTY, EXPLANATION].map(String::from).to_vec();
+  header.extend(keys.iter().map(|key| format!("{}{}", ANSWER_PREFIX, key)));
+  if has_images {
+    header.push(IMAGE.to_owned());
+    header.extend(image_keys.iter().map(|key| format!("{}{}", IMAGE_PREFIX, key)));
+  }
+
+  let mut csv = String::from(BOM);
+  write_row(&mut csv, &header);
+  for item in items {
+    let mut row = vec![
+      item.unidad_tematica.clone(),
+      item.pregunta.clone(),
+      item.tipo_reactivo.clone(),
+      item.respuesta_correcta.clone(),
+      item.dificultad.clone().unwrap_or_default(),
+      item.explicacion.clone().unwrap_or_default(),
+    ];
+    row.extend(keys.iter().map(|key| item.respuestas.get(*key).cloned().unwrap_or_default()));
+    if has_images {
+      row.push(item.imagen.clone().unwrap_or_default());
+      row.extend(image_keys.iter().map(|key| item.imagenes_respuestas.get(*key).cloned().unwrap_or_default()));
+    }
+    write_row(&mut csv, &row);
+  }
+  csv
+}
+
+pub(crate) fn write_row(csv: &mut String, cells: &[String]) {
+  let cells: Vec<String> = cells.iter()
+    .map(|cell| {
+      // Con comillas también se conservan los espacios de las orillas
+      if cell.contains([',', ';', '"', '\n', '\r']) || cell.trim() != cell {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+      } else {
+        cell.clone()
+      }
+    })
+    .collect();
+  csv.push_str(&cells.join(","));
+  csv.push_str("\r\n");
+}
+
+// Las columnas se buscan por nombre, así que pueden venir en cualquier orden.
+// Las filas con errores se omiten y quedan en el reporte.
+pub fn parse(text: &str) -> Result<ImportReport, String> {
+  let text = text.trim_start_matches(BOM);
+  let mut rows = read_rows(text, delimiter(text))?.into_iter();
+  let header = rows.next().ok_or("el archivo está vacío")?;
+  let column = |name: &str| header.iter().position(|cell| cell.trim().eq_ignore_ascii_case(name));
+
+  let (Some(unit), Some(question), Some(tipo), Some(correct)) = (column(UNIT), column(QUESTION), column(TYPE), column(CORRECT)) else {
+    return Err(format!("faltan columnas: se necesitan \"{}\", \"{}\", \"{}\" y \"{}\"", UNIT, QUESTION, TYPE, CORRECT));
+  };
+  let difficulty = column(DIFFICULTY);
+  let explanation = column(EXPLANATION);
+  let image = column(IMAGE);
+  // Columnas "<prefijo><clave>", p. ej. "Respuesta a" o "Imagen a"
+  let keyed_columns = |prefix: &str| -> Vec<(usize, String)> {
+    header.iter()
+      .enumerate()
+      .filter(|(_, cell)| !cell.trim().eq_ignore_ascii_case(CORRECT))
+      .filter_map(|(idx, cell)| {
+        let cell = cell.trim();
+        cell.get(..prefix.len())
+          .filter(|cell_prefix| cell_prefix.eq_ignore_ascii_case(prefix))
+          .map(|_| (idx, cell[prefix.len()..].trim().to_owned()))
+      })
+      .filter(|(_, key)| !key.is_empty())
+      .collect()
+  };
+  let answers = keyed_columns(ANSWER_PREFIX);
+  let answer_images = keyed_columns(IMAGE_PREFIX);
+  if answers.is_empty() {
+    return Err(format!("no hay columnas de respuestas (\"{}a\", \"{}b\", ...)", ANSWER_PREFIX, ANSWER_PREFIX));
+  }
+
+  let mut items = Vec::new();
+  let mut skipped = Vec::new();
+  for (idx, row) in rows.enumerate() {
+    if row.iter().all(|cell| cell.trim().is_empty()) {
+      continue;
+    }
+    // Los textos se copian tal cual; claves, tipo y unidad sin espacios
+    let text = |column: usize| row.get(column).cloned().unwrap_or_default();
+    let field = |column: usize| text(column).trim().to_owned();
+    let optional = |column: Option<usize>| column.map(text).filter(|cell| !cell.trim().is_empty());
+
+    let item = QuizItem {
+      unidad_tematica: field(unit),
+      pregunta: text(question),
+      respuestas: answers.iter()
+        .map(|(column, key)| (key.clone(), text(*column)))
+        .filter(|(_, answer)| !answer.trim().is_empty())
+        .collect::<HashMap<_, _>>(),
+      respuesta_correcta: field(correct),
+      tipo_reactivo: field(tipo),
+      explicacion: optional(explanation),
+      dificultad: optional(difficulty).map(|dificultad| dificultad.trim().to_owned()),
+      imagen: optional(image).map(|imagen| imagen.trim().to_owned()),
+      imagenes_respuestas: answer_images.iter()
+        .map(|(column, key)| (key.clone(), field(*column)))
+        .filter(|(_, imagen)| !imagen.is_empty())
+        .collect(),
+    };
+
+    let error = if item.pregunta.trim().is_empty() {
+      Some(String::from("sin pregunta"))
+    } else if item.unidad_tematica.is_empty() {
+      Some(String::from("sin unidad temática"))
+    } else if !ITEM_TYPES.contains(&item.tipo_reactivo.as_str()) {
+      Some(format!("tipo de reactivo desconocido: \"{}\"", item.tipo_reactivo))
+    } else if item.respuestas.len() < 2 {
+      Some(String::from("menos de dos respuestas"))
+    } else if !item.respuestas.contains_key(&item.respuesta_correcta) {
+      Some(format!("la respuesta correcta \"{}\" no es una de las opciones", item.respuesta_correcta))
+    } else {
+      None
+    };
+    match error {
+      // La fila 1 es el encabezado
+      Some(reason) => skipped.push(Skipped { name: format!("Fila {}", idx + 2), reason }),
+      None => items.push(item),
+    }
+  }
+  Ok(ImportReport { items, skipped })
+}
+
+// Las hojas de cálculo en español suelen exportar con ';'
+fn delimiter(text: &str) -> char {
+  let header = text.lines().next().unwrap_or_default();
+  if header.matches(';').count() > header.matches(',').count() { ';' } else { ',' }
+}
+
+fn read_rows(text: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+  let mut rows = Vec::new();
+  let mut row = Vec::new();
+  let mut cell = String::new();
+  let mut quoted = false;
+  let mut chars = text.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        chars.next();
+        cell.push('"');
+      }
+      '"' if quoted => quoted = false,
+      '"' if cell.is_empty() => quoted = true,
+      _ if quoted => cell.push(c),
+      '\r' => (),
+      '\n' => {
+        row.push(std::mem::take(&mut cell));
+        rows.push(std::mem::take(&mut row));
+      }
+      _ if c == delimiter => row.push(std::mem::take(&mut cell)),
+      _ => cell.push(c),
+    }
+  }
+  if quoted {
+    return Err(String::from("hay comillas sin cerrar"));
+  }
+  if !cell.is_empty() || !row.is_empty() {
+    row.push(cell);
+    rows.push(row);
+  }
+  Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::quiz_item;
+
+  #[test]
+  fn quoted_cells_keep_delimiters_quotes_and_newlines() {
+    let rows = read_rows("a,\"b, c\",\"dijo \"\"hola\"\"\"\r\n\"dos\nlíneas\",,\" x \"\n", ',').unwrap();
+    assert_eq!(rows, vec![
+      vec!["a", "b, c", "dijo \"hola\""],
+      vec!["dos\nlíneas", "", " x "],
+    ]);
+    assert!(read_rows("a,\"sin cerrar\n", ',').is_err());
+  }
+
+  #[test]
+  fn semicolon_files_and_bom_are_accepted() {
+    let text = "\u{feff}Unidad Temática;Pregunta;Tipo de reactivo;Respuesta correcta;Respuesta a;Respuesta b\r\n\
+      Unidad 1;¿Cuánto es 1,5 + 1,5?;Opción Múltiple;b;2;3\r\n";
+    assert_eq!(delimiter(text.trim_start_matches(BOM)), ';');
+    let report = parse(text).unwrap();
+    assert!(report.skipped.is_empty());
+    let [item] = report.items.as_slice() else { panic!("se esperaba una pregunta") };
+    assert_eq!(item.unidad_tematica, "Unidad 1");
+    assert_eq!(item.pregunta, "¿Cuánto es 1,5 + 1,5?");
+    assert_eq!(item.respuestas["b"], "3");
+    assert_eq!(item.respuesta_correcta, "b");
+  }
+
+  #[test]
+  fn invalid_rows_are_reported() {
+    let text = "Pregunta,Unidad Temática,Tipo de reactivo,Respuesta correcta,Respuesta a,Respuesta b\n\
+      Bien,U1,Opción Múltiple,a,x,y\n\
+      ,U1,Opción Múltiple,a,x,y\n\
+      Sin tipo,U1,Dibujo,a,x,y\n\
+      Una opción,U1,Opción Múltiple,a,x,\n\
+      ,,,,,\n\
+      Otra clave,U1,Opción Múltiple,c,x,y\n";
+    let report = parse(text).unwrap();
+    assert_eq!(report.items.len(), 1);
+    let skipped: Vec<&str> = report.skipped.iter().map(|skipped| skipped.name.as_str()).collect();
+    assert_eq!(skipped, vec!["Fila 3", "Fila 4", "Fila 5", "Fila 7"]);
+
+    assert!(parse("").is_err());
+    assert!(parse("Pregunta,Respuesta a\nx,y\n").is_err());
+  }
+
+  #[test]
+  fn write_then_parse_round_trip() {
+    let mut first = quiz_item("Unidad 1", "¿Qué dijo? \"hola\", y luego; adiós", &[("a", " con espacios "), ("b", "dos\nlíneas")], "b");
+    first.explicacion = Some(String::from("Porque sí"));
+    first.dificultad = Some(String::from("Fácil"));
+    first.imagen = Some(String::from("img/uno.png"));
+    first.imagenes_respuestas.insert(String::from("a"), String::from("img/a.png"));
+    let mut second = quiz_item("Unidad 2", "¿Verdad?", &[("V", "Verdadero"), ("F", "Falso")], "V");
+    second.tipo_reactivo = ITEM_TYPES[1].to_owned();
+    let items = vec![first, second];
+
+    let csv = write(&items);
+    assert!(csv.starts_with(BOM));
+    let report = parse(&csv).unwrap();
+    assert!(report.skipped.is_empty());
+    assert_eq!(serde_json::to_value(&report.items).unwrap(), serde_json::to_value(&items).unwrap());
+  }
+}
@@ -0,0 +1,72 @@
+use web_time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+// Día del reto diario, contado en días UTC desde 1970-01-01 para que
+// todos los jugadores compartan la misma semilla sin importar su zona horaria
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DailyDate {
+  pub days: u64,
+}
+
+impl DailyDate {
+  pub fn today() -> Self {
+    let secs = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|d| d.as_secs())
+      .unwrap_or(0);
+    DailyDate { days: secs / SECONDS_PER_DAY }
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.days
+  }
+
+  // Conversión de días a fecha civil (algoritmo de Howard Hinnant)
+  pub fn ymd(&self) -> (i64, u32, u32) {
+    let z = self.days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+  }
+}
+
+impl std::fmt::Display for DailyDate {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let (year, month, day) = self.ymd();
+    write!(f, "{:04}-{:02}-{:02}", year, month, day)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(days: u64) -> DailyDate {
+    DailyDate { days }
+  }
+
+  #[test]
+  fn converts_days_to_civil_dates() {
+    assert_eq!(date(0).ymd(), (1970, 1, 1));
+    // 2024 es bisiesto: el 29 de febrero existe y el 1 de marzo va después
+    assert_eq!(date(19_782).ymd(), (2024, 2, 29));
+    assert_eq!(date(19_783).ymd(), (2024, 3, 1));
+    // 2000 también, por ser múltiplo de 400
+    assert_eq!(date(11_016).ymd(), (2000, 2, 29));
+    assert_eq!(date(19_722).ymd(), (2023, 12, 31));
+    assert_eq!(date(19_723).to_string(), "2024-01-01");
+  }
+
+  #[test]
+  fn same_day_gives_same_seed() {
+    assert_eq!(date(19_782).seed(), date(19_782).seed());
+    assert_ne!(date(19_782).seed(), date(19_783).seed());
+  }
+}
@@ -0,0 +1,599 @@
+use std::{io, net::{IpAddr, SocketAddr}, time::Duration};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use web_time::Instant;
+use crate::{app::QuizItem, net::{Client, ClientEvent, ClientId, Server, ServerEvent}};
+
+pub const DEFAULT_PORT: u16 = 7880;
+const DAMAGE_PER_POINT: f32 = 0.02; // el dado va de 1 a 10: entre 2% y 20% de vida
+const RESULT_PAUSE: Duration = Duration::from_secs(3);
+// Tiempo extra para que llegue la respuesta del rival que contestó al final
+const ANSWER_GRACE: Duration = Duration::from_secs(1);
+
+// Ambos jugadores ven la misma pregunta. La ronda se cierra cuando los dos
+// contestaron o se acabó el tiempo; de los que acertaron, el que tardó menos
+// tira el dado y le quita vida al rival. Así en red no importa quién llega
+// antes al anfitrión, que hace de árbitro igual que en la raid y reparte el
+// estado completo.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum DuelClientMessage {
+  Join { name: String },
+  Answer { round: u32, key: String, elapsed_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum DuelHostMessage {
+  Welcome,
+  State { snapshot: Box<DuelSnapshot> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuelStatus {
+  Lobby,
+  Question,
+  RoundOver,
+  Finished,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuelFighter {
+  pub name: String,
+  pub health: f32,
+  pub correct: u16,
+  pub wrong: u16,
+  pub damage_dealt: u32,
+  pub connected: bool,
+}
+
+impl DuelFighter {
+  fn new(name: &str) -> Self {
+    DuelFighter {
+      name: name.to_owned(),
+      health: 1.0,
+      correct: 0,
+      wrong: 0,
+      damage_dealt: 0,
+      connected: true,
+    }
+  }
+
+  pub fn is_alive(&self) -> bool {
+    self.health > 0.0
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundResult {
+  pub winner: Option<usize>,
+  pub roll: u32,
+  pub correct_key: String,
+  pub answers: [Option<(String, u64)>; 2], // (clave, milisegundos)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuelSnapshot {
+  pub status: DuelStatus,
+  pub round: u32,
+  pub question: Option<QuizItem>,
+  pub duration_secs: u64,
+  pub fighters: [DuelFighter; 2],
+  pub answered: [bool; 2],
+  pub result: Option<RoundResult>,
+}
+
+impl DuelSnapshot {
+  pub fn winner(&self) -> Option<usize> {
+    if self.status != DuelStatus::Finished {
+      return None;
+    }
+    self.fighters.iter().position(|fighter| fighter.is_alive() && fighter.connected)
+  }
+}
+
+pub struct DuelSettings {
+  pub port: u16,
+  pub address: String,
+  pub rival_name: String,
+  pub error: Option<String>,
+}
+
+impl Default for DuelSettings {
+  fn default() -> Self {
+    DuelSettings {
+      port: DEFAULT_PORT,
+      address: format!("127.0.0.1:{}", DEFAULT_PORT),
+      rival_name: String::from("Jugador 2"),
+      error: None,
+    }
+  }
+}
+
+// Árbitro del duelo, lo usan tanto la partida local como el anfitrión en red
+pub struct DuelMatch {
+  snapshot: DuelSnapshot,
+  items: Vec<QuizItem>,
+  current: usize,
+  rng: StdRng,
+  round_start: Instant,
+  answers: [Option<(String, Duration)>; 2],
+  round_over_at: Option<Instant>,
+}
+
+impl DuelMatch {
+  pub fn new(quiz_items: &[QuizItem], names: [&str; 2]) -> Self {
+    let mut rng = StdRng::from_entropy();
+    let mut items = quiz_items.to_vec();
+    items.shuffle(&mut rng);
+    DuelMatch {
+      snapshot: DuelSnapshot {
+        status: DuelStatus::Lobby,
+        round: 0,
+        question: None,
+        duration_secs: 0,
+        fighters: [DuelFighter::new(names[0]), DuelFighter::new(names[1])],
+        answered: [false; 2],
+        result: None,
+      },
+      items,
+      current: 0,
+      rng,
+      round_start: Instant::now(),
+      answers: [None, None],
+      round_over_at: None,
+    }
+  }
+
+  pub fn snapshot(&self) -> &DuelSnapshot {
+    &self.snapshot
+  }
+
+  fn item(&self) -> &QuizItem {
+    &self.items[self.current]
+  }
+
+  pub fn remaining(&self) -> Duration {
+    match self.snapshot.status {
+      DuelStatus::Question => self.item().time_limit().saturating_sub(self.round_start.elapsed()),
+      _ => Duration::ZERO,
+    }
+  }
+
+  pub fn begin(&mut self) {
+    if self.snapshot.status == DuelStatus::Lobby && !self.items.is_empty() {
+      self.next_round();
+    }
+  }
+
+  fn next_round(&mut self) {
+    if self.snapshot.round > 0 {
+      self.current += 1;
+    }
+    if self.current >= self.items.len() {
+      self.items.shuffle(&mut self.rng);
+      self.current = 0;
+    }
+
+    // La clave correcta no viaja hasta que termina la ronda
+    let mut question = self.item().clone();
+    question.respuesta_correcta.clear();
+    question.explicacion = None;
+
+    self.snapshot.status = DuelStatus::Question;
+    self.snapshot.round += 1;
+    self.snapshot.question = Some(question);
+    self.snapshot.duration_secs = self.item().time_limit().as_secs();
+    self.snapshot.answered = [false; 2];
+    self.snapshot.result = None;
+    self.answers = [None, None];
+    self.round_start = Instant::now();
+    self.round_over_at = None;
+  }
+
+  // Devuelve true si el estado cambió y hay que avisar al rival
+  pub fn answer(&mut self, side: usize, key: &str, elapsed: Duration) -> bool {
+    if self.snapshot.status != DuelStatus::Question || self.answers[side].is_some() {
+      return false;
+    }
+    // El tiempo lo reporta cada jugador, pero no puede ser mayor al que realmente pasó
+    let elapsed = elapsed.min(self.round_start.elapsed());
+    self.answers[side] = Some((key.to_owned(), elapsed));
+    self.snapshot.answered[side] = true;
+    if key == self.item().respuesta_correcta {
+      self.snapshot.fighters[side].correct += 1;
+    } else {
+      self.snapshot.fighters[side].wrong += 1;
+    }
+
+    if self.answers.iter().all(Option::is_some) {
+      self.resolve();
+    }
+    true
+  }
+
+  pub fn tick(&mut self) -> bool {
+    match self.snapshot.status {
+      DuelStatus::Question if self.round_start.elapsed() >= self.item().time_limit() + ANSWER_GRACE => {
+        self.resolve();
+        true
+      }
+      DuelStatus::RoundOver if self.round_over_at.is_some_and(|at| at.elapsed() >= RESULT_PAUSE) => {
+        self.next_round();
+        true
+      }
+      _ => false,
+    }
+  }
+
+  // Gana la ronda el acierto más rápido; si empatan al milisegundo, nadie
+  fn round_winner(&self) -> Option<usize> {
+    let correct_key = &self.item().respuesta_correcta;
+    let times: Vec<(usize, Duration)> = self.answers.iter().enumerate()
+      .filter_map(|(side, answer)| answer.as_ref().filter(|(key, _)| key == correct_key).map(|(_, elapsed)| (side, *elapsed)))
+      .collect();
+    match times.as_slice() {
+      [(side, _)] => Some(*side),
+      [(first, a), (second, b)] if a != b => Some(if a < b { *first } else { *second }),
+      _ => None,
+    }
+  }
+
+  fn resolve(&mut self) {
+    let winner = self.round_winner();
+    let mut roll = 0;
+    if let Some(side) = winner {
+      roll = self.rng.gen_range(1..=10);
+      let rival = &mut self.snapshot.fighters[1 - side];
+      rival.health = (rival.health - roll as f32 * DAMAGE_PER_POINT).clamp(0.0, 1.0);
+      self.snapshot.fighters[side].damage_dealt += roll;
+    }
+
+    self.snapshot.question = Some(self.item().clone());
+    self.snapshot.result = Some(RoundResult {
+      winner,
+      roll,
+      correct_key: self.item().respuesta_correcta.clone(),
+      answers: self.answers.clone().map(|answer| {
+        answer.map(|(key, elapsed)| (key, elapsed.as_millis() as u64))
+      }),
+    });
+    self.snapshot.status = if self.snapshot.fighters.iter().all(DuelFighter::is_alive) {
+      DuelStatus::RoundOver
+    } else {
+      DuelStatus::Finished
+    };
+    self.round_over_at = Some(Instant::now());
+  }
+
+  fn forfeit(&mut self, side: usize) {
+    self.snapshot.fighters[side].connected = false;
+    if self.snapshot.status != DuelStatus::Lobby {
+      self.snapshot.status = DuelStatus::Finished;
+    }
+  }
+}
+
+pub struct DuelHost {
+  server: Server<DuelClientMessage, DuelHostMessage>,
+  rival: Option<ClientId>,
+  duel: DuelMatch,
+  pub lan_ip: Option<IpAddr>,
+}
+
+impl DuelHost {
+  pub const HOST_SIDE: usize = 0;
+
+  pub fn start(port: u16, host_name: &str, quiz_items: &[QuizItem]) -> io::Result<Self> {
+    Ok(DuelHost {
+      server: Server::bind(("0.0.0.0", port))?,
+      rival: None,
+      duel: DuelMatch::new(quiz_items, [host_name, ""]),
+      lan_ip: crate::net::local_ip(),
+    })
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.server.local_addr()
+  }
+
+  pub fn snapshot(&self) -> &DuelSnapshot {
+    self.duel.snapshot()
+  }
+
+  pub fn has_rival(&self) -> bool {
+    self.rival.is_some()
+  }
+
+  pub fn update(&mut self) {
+    let mut changed = false;
+    for event in self.server.poll() {
+      match event {
+        ServerEvent::Connected(_) => (),
+        ServerEvent::Message(id, DuelClientMessage::Join { name }) => {
+          // Solo cabe un rival y únicamente antes de empezar
+          if self.rival.is_some() || self.duel.snapshot.status != DuelStatus::Lobby {
+            continue;
+          }
+          self.rival = Some(id);
+          self.duel.snapshot.fighters[1] = DuelFighter::new(&name);
+          self.server.send_to(id, &DuelHostMessage::Welcome);
+          changed = true;
+        }
+        ServerEvent::Message(id, DuelClientMessage::Answer { round, key, elapsed_ms }) => {
+          if self.rival == Some(id) && round == self.duel.snapshot.round {
+            changed |= self.duel.answer(1, &key, Duration::from_millis(elapsed_ms));
+          }
+        }
+        ServerEvent::Disconnected(id) => {
+          if self.rival == Some(id) {
+            self.rival = None;
+            if self.duel.snapshot.status == DuelStatus::Lobby {
+              self.duel.snapshot.fighters[1] = DuelFighter::new("");
+            } else {
+              self.duel.forfeit(1);
+            }
+            changed = true;
+          }
+        }
+      }
+    }
+    changed |= self.duel.tick();
+    if changed {
+      self.broadcast();
+    }
+  }
+
+  pub fn begin(&mut self) {
+    if self.rival.is_some() {
+      self.duel.begin();
+      self.broadcast();
+    }
+  }
+
+  pub fn local_answer(&mut self, key: &str) {
+    let elapsed = self.duel.round_start.elapsed();
+    if self.duel.answer(Self::HOST_SIDE, key, elapsed) {
+      self.broadcast();
+    }
+  }
+
+  fn broadcast(&self) {
+    self.server.broadcast(&DuelHostMessage::State { snapshot: Box::new(self.duel.snapshot.clone()) });
+  }
+}
+
+pub struct DuelClient {
+  client: Client<DuelHostMessage, DuelClientMessage>,
+  pub snapshot: Option<Box<DuelSnapshot>>,
+  pub welcomed: bool,
+  pub connected: bool,
+  round_received: Instant,
+}
+
+impl DuelClient {
+  pub const CLIENT_SIDE: usize = 1;
+
+  pub fn join(address: &str, name: &str) -> io::Result<Self> {
+    let mut client = Client::connect(address)?;
+    client.send(&DuelClientMessage::Join { name: name.to_owned() })?;
+    Ok(DuelClient {
+      client,
+      snapshot: None,
+      welcomed: false,
+      connected: true,
+      round_received: Instant::now(),
+    })
+  }
+
+  pub fn update(&mut self) {
+    for event in self.client.poll() {
+      match event {
+        ClientEvent::Message(DuelHostMessage::Welcome) => self.welcomed = true,
+        ClientEvent::Message(DuelHostMessage::State { snapshot }) => {
+          // El tiempo de respuesta se mide desde que llega la pregunta
+          let new_round = self.snapshot.as_ref().is_none_or(|current| current.round != snapshot.round);
+          if new_round {
+            self.round_received = Instant::now();
+          }
+          self.snapshot = Some(snapshot);
+        }
+        ClientEvent::Disconnected => self.connected = false,
+      }
+    }
+  }
+
+  pub fn remaining(&self) -> Duration {
+    match &self.snapshot {
+      Some(snapshot) if snapshot.status == DuelStatus::Question => {
+        Duration::from_secs(snapshot.duration_secs).saturating_sub(self.round_received.elapsed())
+      }
+      _ => Duration::ZERO,
+    }
+  }
+
+  pub fn answer(&mut self, key: &str) {
+    let Some(snapshot) = self.snapshot.as_mut() else { return };
+    if snapshot.status != DuelStatus::Question || snapshot.answered[Self::CLIENT_SIDE] {
+      return;
+    }
+    // Se marca de inmediato para no mandar dos respuestas a la misma ronda
+    snapshot.answered[Self::CLIENT_SIDE] = true;
+    let msg = DuelClientMessage::Answer {
+      round: snapshot.round,
+      key: key.to_owned(),
+      elapsed_ms: self.round_received.elapsed().as_millis() as u64,
+    };
+    if self.client.send(&msg).is_err() {
+      self.connected = false;
+    }
+  }
+}
+
+pub enum Duel {
+  Inactive,
+  Local(Box<DuelMatch>),
+  Host(Box<DuelHost>),
+  Client(DuelClient),
+}
+
+impl Duel {
+  pub fn update(&mut self) {
+    match self {
+      Duel::Inactive => (),
+      Duel::Local(duel) => {
+        duel.tick();
+      }
+      Duel::Host(host) => host.update(),
+      Duel::Client(client) => client.update(),
+    }
+  }
+
+  pub fn snapshot(&self) -> Option<&DuelSnapshot> {
+    match self {
+      Duel::Inactive => None,
+      Duel::Local(duel) => Some(duel.snapshot()),
+      Duel::Host(host) => Some(host.snapshot()),
+      Duel::Client(client) => client.snapshot.as_deref(),
+    }
+  }
+
+  // Lado que controla esta pantalla; en local se juega con ambos
+  pub fn local_side(&self) -> Option<usize> {
+    match self {
+      Duel::Host(_) => Some(DuelHost::HOST_SIDE),
+      Duel::Client(_) => Some(DuelClient::CLIENT_SIDE),
+      _ => None,
+    }
+  }
+
+  pub fn remaining(&self) -> Duration {
+    match self {
+      Duel::Inactive => Duration::ZERO,
+      Duel::Local(duel) => duel.remaining(),
+      Duel::Host(host) => host.duel.remaining(),
+      Duel::Client(client) => client.remaining(),
+    }
+  }
+
+  pub fn answer(&mut self, side: usize, key: &str) {
+    match self {
+      Duel::Inactive => (),
+      Duel::Local(duel) => {
+        let elapsed = duel.round_start.elapsed();
+        duel.answer(side, key, elapsed);
+      }
+      Duel::Host(host) => host.local_answer(key),
+      Duel::Client(client) => client.answer(key),
+    }
+  }
+
+  pub fn is_connected(&self) -> bool {
+    match self {
+      Duel::Client(client) => client.connected,
+      _ => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::{quiz_item, wait_for};
+
+  fn item(tipo_reactivo: &str) -> QuizItem {
+    QuizItem {
+      tipo_reactivo: tipo_reactivo.to_owned(),
+      ..quiz_item("1 - Prueba", "¿2 + 2?", &[("a", "4"), ("b", "5")], "a")
+    }
+  }
+
+  fn started(tipo_reactivo: &str) -> DuelMatch {
+    let mut duel = DuelMatch::new(&[item(tipo_reactivo)], ["Ana", "Beto"]);
+    duel.begin();
+    duel
+  }
+
+  #[test]
+  fn faster_correct_answer_wins_even_if_it_arrives_later() {
+    let mut duel = started("Opción Múltiple");
+    std::thread::sleep(Duration::from_millis(30));
+    assert!(duel.answer(0, "a", Duration::from_millis(25)));
+    // La ronda no se cierra hasta que contestan los dos
+    assert_eq!(duel.snapshot().status, DuelStatus::Question);
+    assert!(duel.answer(1, "a", Duration::from_millis(10)));
+
+    let result = duel.snapshot().result.clone().unwrap();
+    assert_eq!(result.winner, Some(1));
+    assert!(duel.snapshot().fighters[0].health < 1.0);
+    assert_eq!(duel.snapshot().fighters[1].health, 1.0);
+    assert_eq!(duel.snapshot().status, DuelStatus::RoundOver);
+  }
+
+  #[test]
+  fn wrong_answers_never_win() {
+    let mut duel = started("Opción Múltiple");
+    duel.answer(0, "b", Duration::ZERO);
+    duel.answer(1, "a", Duration::from_millis(5));
+    assert_eq!(duel.snapshot().result.as_ref().unwrap().winner, Some(1));
+    assert_eq!((duel.snapshot().fighters[0].wrong, duel.snapshot().fighters[1].correct), (1, 1));
+
+    // Un jugador no puede contestar dos veces la misma ronda
+    assert!(!duel.answer(0, "a", Duration::ZERO));
+  }
+
+  #[test]
+  fn reported_time_cannot_exceed_real_time() {
+    let mut duel = started("Opción Múltiple");
+    duel.answer(0, "a", Duration::from_secs(20));
+    duel.answer(1, "a", Duration::from_secs(10));
+    // Los dos tiempos se recortan a lo que de verdad pasó
+    let answers = duel.snapshot().result.as_ref().unwrap().answers.clone();
+    assert!(answers.iter().flatten().all(|(_, ms)| *ms < 1000));
+  }
+
+  #[test]
+  fn round_closes_when_time_runs_out() {
+    // Un tipo desconocido no tiene tiempo: solo cuenta el margen para el rival
+    let mut duel = started("");
+    duel.answer(0, "a", Duration::ZERO);
+    assert!(!duel.tick());
+    std::thread::sleep(ANSWER_GRACE + Duration::from_millis(50));
+    assert!(duel.tick());
+    assert_eq!(duel.snapshot().result.as_ref().unwrap().winner, Some(0));
+  }
+
+  #[test]
+  fn lan_round_is_decided_by_elapsed_time() {
+    let mut host = DuelHost::start(0, "Ana", &[item("Opción Múltiple")]).unwrap();
+    let address = format!("127.0.0.1:{}", host.local_addr().port());
+    let mut client = DuelClient::join(&address, "Beto").unwrap();
+    wait_for(|| {
+      host.update();
+      client.update();
+      client.welcomed && host.has_rival()
+    });
+
+    host.begin();
+    wait_for(|| {
+      client.update();
+      client.snapshot.as_ref().is_some_and(|snapshot| snapshot.status == DuelStatus::Question)
+    });
+    client.answer("a");
+    // El anfitrión contesta después, pero su respuesta se aplica antes
+    std::thread::sleep(Duration::from_millis(200));
+    host.local_answer("a");
+    wait_for(|| {
+      host.update();
+      host.snapshot().result.is_some()
+    });
+    assert_eq!(host.snapshot().result.as_ref().unwrap().winner, Some(DuelClient::CLIENT_SIDE));
+
+    wait_for(|| {
+      client.update();
+      client.snapshot.as_ref().is_some_and(|snapshot| snapshot.result.is_some())
+    });
+  }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use crate::{app::{validate_bank, QuizItem, ITEM_TYPES}, csv, import::{self, Skipped}};
+
+// Copia de trabajo del banco para el editor. Los cambios no afectan a la
+// partida hasta que se guardan.
+#[derive(Default)]
+pub struct BankEditor {
+  pub items: Vec<QuizItem>,
+  pub selected: Option<usize>,
+  pub search: String,
+  pub new_key: String,
+  pub path: String,
+  pub file_path: String,
+  pub import_report: Option<(String, Vec<Skipped>)>,
+  pub dirty: bool,
+}
+
+impl BankEditor {
+  pub fn new(bank: &[QuizItem]) -> Self {
+    BankEditor {
+      items: bank.to_vec(),
+      selected: if bank.is_empty() { None } else { Some(0) },
+      path: default_path(),
+      ..Default::default()
+    }
+  }
+
+  // Índices de los reactivos que coinciden con la búsqueda (pregunta o unidad)
+  pub fn matches(&self) -> Vec<usize> {
+    let search = self.search.trim().to_lowercase();
+    self.items.iter()
+      .enumerate()
+      .filter(|(_, item)| {
+        search.is_empty()
+          || item.pregunta.to_lowercase().contains(&search)
+          || item.unidad_tematica.to_lowercase().contains(&search)
+      })
+      .map(|(idx, _)| idx)
+      .collect()
+  }
+
+  pub fn units(&self) -> Vec<String> {
+    let mut units: Vec<String> = Vec::new();
+    for item in &self.items {
+      if !item.unidad_tematica.is_empty() && !units.contains(&item.unidad_tematica) {
+        units.push(item.unidad_tematica.clone());
+      }
+    }
+    units.sort();
+    units
+  }
+
+  pub fn selected_item(&self) -> Option<&QuizItem> {
+    self.items.get(self.selected?)
+  }
+
+  pub fn selected_item_mut(&mut self) -> Option<&mut QuizItem> {
+    self.items.get_mut(self.selected?)
+  }
+
+  // El reactivo nuevo hereda la unidad del seleccionado para capturar
+  // varias preguntas seguidas de la misma unidad
+  pub fn add_item(&mut self) {
+    let unidad_tematica = self.selected_item().map(|item| item.unidad_tematica.clone()).unwrap_or_default();
+    let respuestas: HashMap<String, String> = ["a", "b", "c", "d"].iter()
+      .map(|key| (key.to_string(), String::new()))
+      .collect();
+    self.items.push(QuizItem {
+      unidad_tematica,
+      respuestas,
+      respuesta_correcta: String::from("a"),
+      tipo_reactivo: ITEM_TYPES[0].to_owned(),
+      ..Default::default()
+    });
+    self.selected = Some(self.items.len() - 1);
+    self.search.clear();
+    self.dirty = true;
+  }
+
+  pub fn remove_selected(&mut self) {
+    let Some(idx) = self.selected.filter(|&idx| idx < self.items.len()) else { return };
+    self.items.remove(idx);
+    self.selected = if self.items.is_empty() { None } else { Some(idx.min(self.items.len() - 1)) };
+    self.dirty = true;
+  }
+
+  // Cambiar a verdadero o falso deja solo las opciones V y F
+  pub fn set_type(&mut self, tipo: &str) {
+    let Some(item) = self.selected_item_mut() else { return };
+    if item.tipo_reactivo == tipo {
+      return;
+    }
+    item.tipo_reactivo = tipo.to_owned();
+    if tipo == "Verdadero o Falso" {
+      item.respuestas = HashMap::from([
+        (String::from("V"), String::from("Verdadero")),
+        (String::from("F"), String::from("Falso")),
+      ]);
+      if !item.respuestas.contains_key(&item.respuesta_correcta) {
+        item.respuesta_correcta = String::from("V");
+      }
+      item.imagenes_respuestas.retain(|key, _| key == "V" || key == "F");
+    }
+    self.dirty = true;
+  }
+
+  // Usa la clave escrita o, si no hay, la siguiente letra libre
+  pub fn add_answer(&mut self) {
+    let new_key = self.new_key.trim().to_owned();
+    let Some(item) = self.selected_item_mut() else { return };
+    let key = if new_key.is_empty() {
+      match ('a'..='z').map(String::from).find(|key| !item.respuestas.contains_key(key)) {
+        Some(key) => key,
+        None => return,
+      }
+    } else {
+      new_key
+    };
+    if item.respuestas.contains_key(&key) {
+      return;
+    }
+    item.respuestas.insert(key, String::new());
+    self.new_key.clear();
+    self.dirty = true;
+  }
+
+  pub fn remove_answer(&mut self, key: &str) {
+    let Some(item) = self.selected_item_mut() else { return };
+    item.respuestas.remove(key);
+    item.imagenes_respuestas.remove(key);
+    if item.respuesta_correcta == key {
+      item.respuesta_correcta = item.respuestas.keys().min().cloned().unwrap_or_default();
+    }
+    self.dirty = true;
+  }
+
+  // Agrega al final las preguntas de un archivo de Moodle o CSV; no se
+  // escriben hasta que se guarda
+  pub fn import(&mut self) -> Result<(), String> {
+    let report = import::from_path(std::path::Path::new(self.file_path.trim()))?;
+    let summary = report.summary();
+    if !report.items.is_empty() {
+      self.selected = Some(self.items.len());
+      self.search.clear();
+      self.dirty = true;
+    }
+    self.items.extend(report.items);
+    self.import_report = Some((summary, report.skipped));
+    Ok(())
+  }
+
+  // Exporta la copia de trabajo tal como está, aunque no se haya guardado
+  pub fn export_csv(&self) -> Result<(), String> {
+    let path = self.file_path.trim();
+    std::fs::write(path, csv::write(&self.items)).map_err(|e| format!("No se pudo escribir {}: {}", path, e))
+  }
+
+  // Valida y escribe el banco; si hay un error se selecciona la pregunta
+  // que lo tiene
+  pub fn save(&mut self) -> Result<(), String> {
+    if let Err((idx, message)) = validate_items(&self.items) {
+      self.selected = Some(idx);
+      self.search.clear();
+      return Err(message);
+    }
+    validate_bank(&self.items)?;
+    write_bank(&self.path, &self.items)?;
+    self.dirty = false;
+    Ok(())
+  }
+}
+
+// Revisiones que solo importan al capturar: textos vacíos que un JSON
+// escrito a mano difícilmente tendría
+fn validate_items(items: &[QuizItem]) -> Result<(), (usize, String)> {
+  for (idx, item) in items.iter().enumerate() {
+    let error = if item.unidad_tematica.trim().is_empty() {
+      "no tiene unidad temática"
+    } else if item.pregunta.trim().is_empty() {
+      "no tiene texto"
+    } else if item.respuestas.values().any(|answer| answer.trim().is_empty()) {
+      "tiene respuestas vacías"
+    } else {
+      continue;
+    };
+    return Err((idx, format!("la pregunta {} {}", idx + 1, error)));
+  }
+  Ok(())
+}
+
+// Se guarda sobre el banco de la carpeta de reemplazo, que es de donde lo
+// lee el juego; sin `--assets` es la copia en la carpeta de datos del usuario
+#[cfg(not(target_arch = "wasm32"))]
+fn default_path() -> String {
+  use crate::assets;
+
+  assets::override_dir()
+    .map(|dir| dir.join(assets::QUESTIONS).display().to_string())
+    .unwrap_or_else(|| assets::QUESTIONS.to_owned())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_path() -> String {
+  String::new()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_bank(path: &str, items: &[QuizItem]) -> Result<(), String> {
+  let json = crate::app::bank_to_json(items);
+  let path = std::path::Path::new(path);
+  if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+    std::fs::create_dir_all(dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+  }
+  std::fs::write(path, json).map_err(|e| format!("No se pudo guardar {}: {}", path.display(), e))
+}
+
+// En el navegador no hay archivos: el banco editado solo dura la sesión
+#[cfg(target_arch = "wasm32")]
+fn write_bank(_path: &str, _items: &[QuizItem]) -> Result<(), String> {
+  Ok(())
+}
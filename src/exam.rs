@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use rand::{seq::SliceRandom, Rng};
+use crate::app::QuizItem;
+
+#[derive(Default)]
+pub struct ExamSettings {
+  pub units: Vec<String>,
+  pub types: Vec<String>,
+  pub distribution: Vec<Vec<usize>>, // [unidad][tipo] -> preguntas a sacar
+  pub available: Vec<Vec<usize>>,
+  pub minutes: u64,
+}
+
+impl ExamSettings {
+  pub fn from_bank(quiz_items: &[QuizItem]) -> Self {
+    let mut units: Vec<String> = Vec::new();
+    let mut types: Vec<String> = Vec::new();
+    for item in quiz_items {
+      if !units.contains(&item.unidad_tematica) {
+        units.push(item.unidad_tematica.clone());
+      }
+      if !types.contains(&item.tipo_reactivo) {
+        types.push(item.tipo_reactivo.clone());
+      }
+    }
+    units.sort();
+    types.sort();
+
+    let mut available = vec![vec![0; types.len()]; units.len()];
+    for item in quiz_items {
+      let unit_idx = units.iter().position(|unit| *unit == item.unidad_tematica).unwrap();
+      let type_idx = types.iter().position(|tipo| *tipo == item.tipo_reactivo).unwrap();
+      available[unit_idx][type_idx] += 1;
+    }
+
+    ExamSettings {
+      units,
+      types,
+      distribution: available.clone(),
+      available,
+      minutes: 30,
+    }
+  }
+
+  pub fn total_questions(&self) -> usize {
+    self.distribution.iter().flatten().sum()
+  }
+
+  pub fn duration(&self) -> Duration {
+    Duration::from_secs(self.minutes * 60)
+  }
+
+  pub fn draw(&self, quiz_items: &[QuizItem], rng: &mut impl Rng) -> Vec<QuizItem> {
+    let mut exam_items = Vec::new();
+    for (unit_idx, unit) in self.units.iter().enumerate() {
+      for (type_idx, tipo) in self.types.iter().enumerate() {
+        let mut cell: Vec<&QuizItem> = quiz_items.iter()
+          .filter(|item| item.unidad_tematica == *unit && item.tipo_reactivo == *tipo)
+          .collect();
+        cell.shuffle(rng);
+        exam_items.extend(cell.into_iter().take(self.distribution[unit_idx][type_idx]).cloned());
+      }
+    }
+    exam_items.shuffle(rng);
+    exam_items
+  }
+}
+
+pub struct ExamAnswer {
+  pub item: QuizItem,
+  pub chosen: Option<String>,
+}
+
+impl ExamAnswer {
+  pub fn is_correct(&self) -> bool {
+    self.chosen.as_deref() == Some(self.item.respuesta_correcta.as_str())
+  }
+}
+
+#[derive(Default)]
+pub struct ExamState {
+  pub answers: Vec<ExamAnswer>,
+  pub time_used: Duration,
+}
+
+impl ExamState {
+  pub fn correct_answers(&self) -> usize {
+    self.answers.iter().filter(|answer| answer.is_correct()).count()
+  }
+
+  // Calificación en escala de 0 a 10
+  pub fn grade(&self) -> f32 {
+    if self.answers.is_empty() {
+      return 0.0;
+    }
+    self.correct_answers() as f32 / self.answers.len() as f32 * 10.0
+  }
+
+  // (unidad, aciertos, total) ordenado por unidad; los nombres empiezan con
+  // su número
+  pub fn per_unit(&self) -> Vec<(String, usize, usize)> {
+    let mut report: Vec<(String, usize, usize)> = Vec::new();
+    for answer in &self.answers {
+      let idx = match report.iter().position(|(unit, _, _)| *unit == answer.item.unidad_tematica) {
+        Some(idx) => idx,
+        None => {
+          report.push((answer.item.unidad_tematica.clone(), 0, 0));
+          report.len() - 1
+        }
+      };
+      report[idx].2 += 1;
+      if answer.is_correct() {
+        report[idx].1 += 1;
+      }
+    }
+    report.sort();
+    report
+  }
+}
@@ -0,0 +1,1104 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{app::{QuizItem, ITEM_TYPES}, csv};
+
+// Conversión de bancos de Moodle (XML, GIFT y Aiken) al formato del juego. El juego
+// solo tiene preguntas de opciones, así que:
+// - numéricas: se agregan distractores cercanos al valor correcto
+// - respuesta corta: los distractores son respuestas de otras preguntas de
+//   respuesta corta de la misma categoría
+// Lo que no se puede convertir (ensayo, emparejamiento, varias correctas...)
+// queda en el reporte con el motivo.
+
+pub struct ImportReport {
+  pub items: Vec<QuizItem>,
+  pub skipped: Vec<Skipped>,
+}
+
+pub struct Skipped {
+  pub name: String,
+  pub reason: String,
+}
+
+impl ImportReport {
+  pub fn summary(&self) -> String {
+    format!("{} preguntas importadas, {} omitidas", self.items.len(), self.skipped.len())
+  }
+}
+
+// Elige el formato por la extensión; en los de Moodle la unidad por defecto
+// es el nombre del archivo
+pub fn from_path(path: &Path) -> Result<ImportReport, String> {
+  let text = std::fs::read_to_string(path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+  let unit = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+  match path.extension().and_then(|ext| ext.to_str()).map(str::to_lowercase).as_deref() {
+    Some("xml") => moodle_xml(&text, &unit),
+    Some("gift") => Ok(gift(&text, &unit)),
+    Some("csv") => csv::parse(&text),
+    Some("txt" | "aiken") => Ok(aiken(&text, &unit)),
+    _ => Err(format!("Formato no reconocido: {} (se espera .xml, .gift, .txt o .csv)", path.display())),
+  }
+}
+
+enum Answers {
+  Choices { options: Vec<String>, correct: usize },
+  ShortAnswer { answer: String },
+}
+
+// Pregunta leída pero todavía sin convertir: las de respuesta corta
+// necesitan conocer a las demás para armar sus opciones
+struct Draft {
+  name: String,
+  unit: String,
+  pregunta: String,
+  tipo: &'static str,
+  answers: Answers,
+  explicacion: Option<String>,
+}
+
+fn finish(drafts: Vec<Draft>, mut skipped: Vec<Skipped>) -> ImportReport {
+  let short_answers: Vec<(&str, &str)> = drafts.iter()
+    .filter_map(|draft| match &draft.answers {
+      Answers::ShortAnswer { answer } => Some((draft.unit.as_str(), answer.as_str())),
+      Answers::Choices { .. } => None,
+    })
+    .collect();
+
+  let mut items = Vec::new();
+  for draft in &drafts {
+    let (options, correct) = match &draft.answers {
+      Answers::Choices { options, correct } => (options.clone(), *correct),
+      Answers::ShortAnswer { answer } => {
+        let mut options: Vec<String> = Vec::new();
+        for (unit, other) in &short_answers {
+          let repeated = options.iter().any(|option| option.eq_ignore_ascii_case(other));
+          if *unit == draft.unit && !other.eq_ignore_ascii_case(answer) && !repeated && options.len() < 3 {
+            options.push(other.to_string());
+          }
+        }
+        if options.is_empty() {
+          skipped.push(Skipped {
+            name: draft.name.clone(),
+            reason: String::from("respuesta corta sin otras preguntas de su categoría para armar opciones"),
+          });
+          continue;
+        }
+        options.push(answer.clone());
+        options.sort_by_key(|option| option.to_lowercase());
+        let correct = options.iter().position(|option| option == answer).unwrap_or(0);
+        (options, correct)
+      }
+    };
+
+    let keys: Vec<String> = if draft.tipo == ITEM_TYPES[1] {
+      vec![String::from("V"), String::from("F")]
+    } else {
+      ('a'..='z').take(options.len()).map(String::from).collect()
+    };
+    items.push(QuizItem {
+      unidad_tematica: draft.unit.clone(),
+      pregunta: draft.pregunta.clone(),
+      respuestas: keys.iter().cloned().zip(options).collect::<HashMap<_, _>>(),
+      respuesta_correcta: keys[correct].clone(),
+      tipo_reactivo: draft.tipo.to_owned(),
+      explicacion: draft.explicacion.clone(),
+      ..Default::default()
+    });
+  }
+  ImportReport { items, skipped }
+}
+
+fn true_false(correct: bool) -> Answers {
+  Answers::Choices {
+    options: vec![String::from("Verdadero"), String::from("Falso")],
+    correct: if correct { 0 } else { 1 },
+  }
+}
+
+// Opciones para una numérica con una sola respuesta: el valor correcto y tres
+// cercanos con los mismos decimales, ordenados de menor a mayor. Con pocos
+// decimales varios candidatos pueden redondear al mismo texto, así que se
+// siguen probando valores cada vez más lejanos hasta tener tres distintos.
+fn numeric_choices(value: &str) -> Option<Answers> {
+  let value = value.trim();
+  let number: f64 = value.parse().ok().filter(|number: &f64| number.is_finite())?;
+  let decimals = value.split_once('.').map_or(0, |(_, fraction)| fraction.len());
+  let step = 10f64.powi(-(decimals as i32));
+  // Sumar 0.0 convierte -0.0 en 0.0, para no mostrar "-0"
+  let text = |candidate: f64| format!("{:.*}", decimals, (candidate / step).round() * step + 0.0);
+  let candidates = [number * 0.5, number * 1.5, number * 2.0].into_iter()
+    .chain((1..=50).flat_map(|k| [number + k as f64 * step, number - k as f64 * step]));
+
+  let mut options = vec![(number, text(number))];
+  for candidate in candidates {
+    if options.len() == 4 {
+      break;
+    }
+    let candidate_text = text(candidate);
+    if !options.iter().any(|(_, option)| *option == candidate_text) {
+      options.push((candidate, candidate_text));
+    }
+  }
+  if options.len() < 4 {
+    return None;
+  }
+  options.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+  let correct = options.iter().position(|(candidate, _)| *candidate == number)?;
+  Some(Answers::Choices { options: options.into_iter().map(|(_, option)| option).collect(), correct })
+}
+
+// Numérica con las respuestas del banco, marcadas como aceptadas o no. Las
+// que no dan puntos son los distractores; las otras aceptadas son el mismo
+// valor con otra precisión y se quitan para no mostrarlas como incorrectas.
+// Si no queda ningún distractor se generan con `numeric_choices`.
+fn bank_numeric_choices(answers: &[(bool, String)], correct: usize) -> Option<Answers> {
+  let correct = answers.get(correct)?.1.trim();
+  let mut options: Vec<String> = Vec::new();
+  for (accepted, text) in answers {
+    let text = text.trim();
+    if (text == correct || !accepted) && !text.is_empty() && !options.iter().any(|option| option == text) {
+      options.push(text.to_owned());
+    }
+  }
+  if options.len() < 2 {
+    return numeric_choices(correct);
+  }
+  let correct = options.iter().position(|option| option == correct)?;
+  Some(Answers::Choices { options, correct })
+}
+
+// Moodle guarda las categorías como rutas ("$course$/top/Unidad 1"); la
+// unidad es el último tramo
+fn category_unit(category: &str) -> Option<String> {
+  category.rsplit('/').map(str::trim).find(|part| !part.is_empty() && !part.starts_with('$') && *part != "top").map(String::from)
+}
+
+// --- Moodle XML ---
+
+pub fn moodle_xml(text: &str, default_unit: &str) -> Result<ImportReport, String> {
+  let root = xml::parse(text)?;
+  if root.name != "quiz" {
+    return Err(format!("Se esperaba <quiz> y se encontró <{}>", root.name));
+  }
+
+  let mut unit = default_unit.to_owned();
+  let mut drafts = Vec::new();
+  let mut skipped = Vec::new();
+  for (idx, question) in root.children("question").enumerate() {
+    let kind = question.attr("type").unwrap_or_default();
+    if kind == "category" {
+      if let Some(category) = question.child("category").and_then(|category| category.child("text")) {
+        unit = category_unit(&category.text()).unwrap_or_else(|| default_unit.to_owned());
+      }
+      continue;
+    }
+
+    let name = question.child("name")
+      .and_then(|name| name.child("text"))
+      .map(|text| text.text().trim().to_owned())
+      .filter(|name| !name.is_empty())
+      .unwrap_or_else(|| format!("Pregunta {}", idx + 1));
+    let pregunta = question.child("questiontext")
+      .and_then(|questiontext| questiontext.child("text"))
+      .map(|text| strip_html(&text.text()))
+      .unwrap_or_default();
+    let explicacion = question.child("generalfeedback")
+      .and_then(|feedback| feedback.child("text"))
+      .map(|text| strip_html(&text.text()))
+      .filter(|text| !text.is_empty());
+    let answers: Vec<(f64, String)> = question.children("answer")
+      .map(|answer| {
+        let fraction = answer.attr("fraction").and_then(|fraction| fraction.parse().ok()).unwrap_or(0.0);
+        let text = answer.child("text").map(|text| strip_html(&text.text())).unwrap_or_default();
+        (fraction, text)
+      })
+      .collect();
+    let full_credit: Vec<usize> = answers.iter().enumerate()
+      .filter(|(_, (fraction, _))| *fraction >= 100.0)
+      .map(|(idx, _)| idx)
+      .collect();
+
+    let converted = match kind {
+      "multichoice" if question.child("single").is_some_and(|single| single.text().trim() == "false") => {
+        Err("opción múltiple con varias respuestas correctas")
+      }
+      "multichoice" => match full_credit.first() {
+        Some(&correct) if answers.len() >= 2 => Ok((ITEM_TYPES[0], Answers::Choices {
+          options: answers.into_iter().map(|(_, text)| text).collect(),
+          correct,
+        })),
+        _ => Err("opción múltiple sin respuesta correcta o con menos de dos opciones"),
+      },
+      "truefalse" => match full_credit.first() {
+        Some(&correct) => Ok((ITEM_TYPES[1], true_false(answers[correct].1.trim().eq_ignore_ascii_case("true")))),
+        None => Err("verdadero o falso sin respuesta correcta"),
+      },
+      "numerical" => match full_credit.first() {
+        // La primera con todos los puntos es la correcta; con crédito parcial
+        // también se aceptan, así que no sirven de distractores
+        Some(&correct) => {
+          let accepted: Vec<(bool, String)> = answers.into_iter().map(|(fraction, text)| (fraction > 0.0, text)).collect();
+          bank_numeric_choices(&accepted, correct)
+            .map(|answers| (ITEM_TYPES[2], answers))
+            .ok_or("numérica con una respuesta que no es un número")
+        }
+        None => Err("numérica sin respuesta correcta"),
+      },
+      "shortanswer" => match full_credit.first() {
+        Some(&correct) => Ok((ITEM_TYPES[0], Answers::ShortAnswer { answer: answers[correct].1.clone() })),
+        None => Err("respuesta corta sin respuesta correcta"),
+      },
+      "" => Err("pregunta sin tipo"),
+      _ => Err("tipo de pregunta no soportado"),
+    };
+
+    match converted {
+      Ok((tipo, answers)) if !pregunta.is_empty() => drafts.push(Draft { name, unit: unit.clone(), pregunta, tipo, answers, explicacion }),
+      Ok(_) => skipped.push(Skipped { name, reason: String::from("pregunta sin texto") }),
+      Err(reason) => skipped.push(Skipped {
+        name,
+        reason: if kind.is_empty() { reason.to_owned() } else { format!("{} ({})", reason, kind) },
+      }),
+    }
+  }
+  Ok(finish(drafts, skipped))
+}
+
+// Moodle guarda los textos como HTML; el juego solo muestra texto plano
+fn strip_html(html: &str) -> String {
+  let mut text = String::new();
+  let mut rest = html;
+  while let Some(start) = rest.find('<') {
+    text.push_str(&rest[..start]);
+    let Some(end) = rest[start..].find('>') else {
+      rest = &rest[start..];
+      break;
+    };
+    let tag = rest[start + 1..start + end].trim_start_matches('/').to_lowercase();
+    if tag.starts_with("br") || tag.starts_with('p') || tag.starts_with("div") || tag.starts_with("li") {
+      text.push('\n');
+    }
+    rest = &rest[start + end + 1..];
+  }
+  text.push_str(rest);
+
+  let text = xml::decode_entities(&text);
+  let lines: Vec<String> = text.lines()
+    .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+    .filter(|line| !line.is_empty())
+    .collect();
+  lines.join("\n")
+}
+
+// --- Aiken ---
+
+// Los archivos Aiken se pueden dejar junto al banco principal
+pub fn is_aiken(name: &str) -> bool {
+  Path::new(name).extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| ext.eq_ignore_ascii_case("txt") || ext.eq_ignore_ascii_case("aiken"))
+}
+
+// Una pregunta por bloque: el texto (puede ocupar varias líneas), las
+// opciones "A." o "A)" y al final "ANSWER: A". Una línea "UNIDAD: nombre"
+// cambia la unidad de las preguntas que siguen.
+pub fn aiken(text: &str, default_unit: &str) -> ImportReport {
+  let mut unit = default_unit.to_owned();
+  let mut drafts = Vec::new();
+  let mut skipped = Vec::new();
+  let mut question: Option<(usize, String)> = None; // línea donde empieza y texto
+  let mut options: Vec<(char, String)> = Vec::new();
+  let unanswered = |line: usize| Skipped { name: format!("Línea {}", line), reason: String::from("pregunta sin ANSWER") };
+
+  for (idx, line) in text.lines().enumerate() {
+    let line = line.trim();
+    let number = idx + 1;
+    if line.is_empty() {
+      continue;
+    }
+    if let Some(name) = strip_label(line, "UNIDAD:") {
+      if let Some((start, _)) = question.take() {
+        skipped.push(unanswered(start));
+      }
+      options.clear();
+      unit = name.trim().to_owned();
+      continue;
+    }
+    if let Some(answer) = strip_label(line, "ANSWER:") {
+      let Some((start, pregunta)) = question.take() else {
+        skipped.push(Skipped { name: format!("Línea {}", number), reason: String::from("ANSWER sin pregunta") });
+        continue;
+      };
+      let name = format!("Línea {}", start);
+      let answer = answer.trim();
+      let options = std::mem::take(&mut options);
+      match options.iter().position(|(letter, _)| answer.eq_ignore_ascii_case(&letter.to_string())) {
+        Some(_) if options.len() < 2 => skipped.push(Skipped { name, reason: String::from("menos de dos opciones") }),
+        Some(correct) => {
+          let texts: Vec<String> = options.into_iter().map(|(_, text)| text).collect();
+          let (tipo, answers) = aiken_answers(texts, correct);
+          drafts.push(Draft { name, unit: unit.clone(), pregunta, tipo, answers, explicacion: None });
+        }
+        None => skipped.push(Skipped { name, reason: format!("la respuesta \"{}\" no es una de las opciones", answer) }),
+      }
+      continue;
+    }
+    if let (Some(option), Some(_)) = (aiken_option(line), &question) {
+      options.push(option);
+      continue;
+    }
+    // Texto suelto: continúa la pregunta si aún no tiene opciones; si ya
+    // tiene, la anterior quedó sin ANSWER y empieza otra
+    match &mut question {
+      Some((_, pregunta)) if options.is_empty() => {
+        pregunta.push('\n');
+        pregunta.push_str(line);
+      }
+      _ => {
+        if let Some((start, _)) = question.take() {
+          skipped.push(unanswered(start));
+        }
+        options.clear();
+        question = Some((number, line.to_owned()));
+      }
+    }
+  }
+  if let Some((start, _)) = question {
+    skipped.push(unanswered(start));
+  }
+  finish(drafts, skipped)
+}
+
+// Dos opciones "Verdadero" y "Falso" se tratan como reactivo de verdadero o falso
+fn aiken_answers(texts: Vec<String>, correct: usize) -> (&'static str, Answers) {
+  let is = |text: &str, word: &str| text.trim().eq_ignore_ascii_case(word);
+  if let [first, second] = texts.as_slice() {
+    if (is(first, "Verdadero") && is(second, "Falso")) || (is(first, "Falso") && is(second, "Verdadero")) {
+      return (ITEM_TYPES[1], true_false(is(&texts[correct], "Verdadero")));
+    }
+  }
+  (ITEM_TYPES[0], Answers::Choices { options: texts, correct })
+}
+
+fn aiken_option(line: &str) -> Option<(char, String)> {
+  let mut chars = line.chars();
+  let letter = chars.next().filter(char::is_ascii_alphabetic)?;
+  chars.next().filter(|c| *c == '.' || *c == ')')?;
+  let text = chars.as_str();
+  if !text.starts_with(char::is_whitespace) || text.trim().is_empty() {
+    return None;
+  }
+  Some((letter.to_ascii_uppercase(), text.trim().to_owned()))
+}
+
+fn strip_label<'a>(line: &'a str, label: &str) -> Option<&'a str> {
+  line.get(..label.len())
+    .filter(|prefix| prefix.eq_ignore_ascii_case(label))
+    .map(|_| &line[label.len()..])
+}
+
+// --- GIFT ---
+
+pub fn gift(text: &str, default_unit: &str) -> ImportReport {
+  let mut unit = default_unit.to_owned();
+  let mut drafts = Vec::new();
+  let mut skipped = Vec::new();
+  let mut block = String::new();
+  let mut count = 0;
+
+  // Las preguntas se separan con líneas en blanco; los comentarios empiezan con //
+  for line in text.lines().chain(std::iter::once("")) {
+    let trimmed = line.trim();
+    if trimmed.starts_with("//") {
+      continue;
+    }
+    if let Some(category) = trimmed.strip_prefix("$CATEGORY:") {
+      unit = category_unit(category).unwrap_or_else(|| default_unit.to_owned());
+      continue;
+    }
+    if !trimmed.is_empty() {
+      block.push_str(line);
+      block.push('\n');
+      continue;
+    }
+    if block.trim().is_empty() {
+      continue;
+    }
+    count += 1;
+    match gift_question(&block, count, &unit) {
+      Ok(draft) => drafts.push(draft),
+      Err(skip) => skipped.push(skip),
+    }
+    block.clear();
+  }
+  finish(drafts, skipped)
+}
+
+fn gift_question(block: &str, number: usize, unit: &str) -> Result<Draft, Skipped> {
+  let mut text = block.trim();
+  let mut name = format!("Pregunta {}", number);
+  if let Some(rest) = text.strip_prefix("::") {
+    if let Some(end) = find_unescaped(rest, "::") {
+      name = gift_unescape(&rest[..end]).trim().to_owned();
+      text = rest[end + 2..].trim_start();
+    }
+  }
+  let skip = |reason: &str| Skipped { name: name.clone(), reason: reason.to_owned() };
+
+  // Se ignora el formato del texto ([html], [markdown], ...)
+  if text.starts_with('[') {
+    if let Some(end) = text.find(']') {
+      text = text[end + 1..].trim_start();
+    }
+  }
+
+  let Some(open) = find_unescaped(text, "{") else {
+    return Err(skip("sin bloque de respuestas (descripción)"));
+  };
+  let Some(close) = find_unescaped(&text[open..], "}").map(|close| open + close) else {
+    return Err(skip("bloque de respuestas sin cerrar"));
+  };
+  let before = gift_unescape(text[..open].trim());
+  let after = gift_unescape(text[close + 1..].trim());
+  // Pregunta de palabra faltante: el hueco queda marcado con guiones bajos
+  let pregunta = if after.is_empty() { before } else { format!("{} _____ {}", before, after) };
+  let pregunta = pregunta.trim().to_owned();
+
+  let mut body = text[open + 1..close].trim();
+  let mut explicacion = None;
+  if let Some(general) = find_unescaped(body, "####") {
+    explicacion = Some(gift_unescape(body[general + 4..].trim())).filter(|text| !text.is_empty());
+    body = body[..general].trim();
+  }
+
+  let (tipo, answers) = if body.is_empty() {
+    return Err(skip("ensayo"));
+  } else if find_unescaped(body, "->").is_some() {
+    return Err(skip("emparejamiento"));
+  } else if let Some(numeric) = body.strip_prefix('#') {
+    gift_numerical(numeric).map(|answers| (ITEM_TYPES[2], answers)).ok_or_else(|| skip("numérica con un formato no soportado"))?
+  } else if let Some(value) = gift_true_false(body) {
+    (ITEM_TYPES[1], true_false(value))
+  } else {
+    let options = gift_options(body);
+    let correct: Vec<usize> = options.iter().enumerate().filter(|(_, (is_correct, _))| *is_correct).map(|(idx, _)| idx).collect();
+    if options.iter().all(|(is_correct, _)| *is_correct) {
+      // Solo respuestas con "=": respuesta corta
+      (ITEM_TYPES[0], Answers::ShortAnswer { answer: options[0].1.clone() })
+    } else if correct.len() != 1 {
+      return Err(skip("opción múltiple sin una única respuesta correcta"));
+    } else {
+      (ITEM_TYPES[0], Answers::Choices {
+        options: options.into_iter().map(|(_, text)| text).collect(),
+        correct: correct[0],
+      })
+    }
+  };
+
+  if pregunta.is_empty() {
+    return Err(skip("pregunta sin texto"));
+  }
+  Ok(Draft { name, unit: unit.to_owned(), pregunta, tipo, answers, explicacion })
+}
+
+fn gift_true_false(body: &str) -> Option<bool> {
+  let value = body.split('#').next().unwrap_or_default().trim();
+  match value.to_uppercase().as_str() {
+    "T" | "TRUE" => Some(true),
+    "F" | "FALSE" => Some(false),
+    _ => None,
+  }
+}
+
+// Opciones marcadas con = (correcta) o ~ (incorrecta), sin retroalimentación
+// ni porcentajes
+fn gift_options(body: &str) -> Vec<(bool, String)> {
+  let mut options = Vec::new();
+  let mut current: Option<(bool, String)> = None;
+  let mut chars = body.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\\' => {
+        if let (Some((_, text)), Some(next)) = (current.as_mut(), chars.next()) {
+          text.push('\\');
+          text.push(next);
+        }
+      }
+      '=' | '~' => {
+        options.extend(current.take());
+        current = Some((c == '=', String::new()));
+      }
+      _ => {
+        if let Some((_, text)) = current.as_mut() {
+          text.push(c);
+        }
+      }
+    }
+  }
+  options.extend(current);
+
+  options.into_iter()
+    .map(|(is_correct, text)| {
+      let text = match find_unescaped(&text, "#") {
+        Some(feedback) => &text[..feedback],
+        None => text.as_str(),
+      };
+      // ~%50%texto: el crédito parcial no cuenta como respuesta correcta
+      let text = text.trim();
+      let text = match text.strip_prefix('%').and_then(|rest| rest.split_once('%')) {
+        Some((_, rest)) => rest,
+        None => text,
+      };
+      (is_correct, gift_unescape(text.trim()))
+    })
+    .filter(|(_, text)| !text.is_empty())
+    .collect()
+}
+
+// {#valor}, {#valor:tolerancia}, {#min..max} o varias respuestas con = y ~
+fn gift_numerical(body: &str) -> Option<Answers> {
+  let body = body.trim();
+  if body.contains('=') || body.contains('~') {
+    let options = gift_options(body);
+    let values: Vec<(bool, String)> = options.into_iter()
+      .map(|(is_correct, text)| (is_correct, text.split(':').next().unwrap_or_default().trim().to_owned()))
+      .collect();
+    let correct = values.iter().position(|(is_correct, _)| *is_correct)?;
+    return bank_numeric_choices(&values, correct);
+  }
+  let body = body.split('#').next().unwrap_or_default().trim();
+  if let Some((min, max)) = body.split_once("..") {
+    // Un rango se convierte a su punto medio
+    let (min, max): (f64, f64) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+    return numeric_choices(&((min + max) / 2.0).to_string());
+  }
+  numeric_choices(body.split(':').next().unwrap_or_default())
+}
+
+fn find_unescaped(text: &str, pattern: &str) -> Option<usize> {
+  let mut escaped = false;
+  for (idx, c) in text.char_indices() {
+    if escaped {
+      escaped = false;
+    } else if c == '\\' {
+      escaped = true;
+    } else if text[idx..].starts_with(pattern) {
+      return Some(idx);
+    }
+  }
+  None
+}
+
+fn gift_unescape(text: &str) -> String {
+  let mut result = String::new();
+  let mut chars = text.chars();
+  while let Some(c) = chars.next() {
+    match (c, chars.clone().next()) {
+      ('\\', Some('n')) => {
+        chars.next();
+        result.push('\n');
+      }
+      ('\\', Some(next)) if "~=#{}:\\".contains(next) => {
+        chars.next();
+        result.push(next);
+      }
+      _ => result.push(c),
+    }
+  }
+  result
+}
+
+// Lector de XML mínimo, suficiente para los archivos que exporta Moodle:
+// elementos, atributos, texto, CDATA, comentarios y entidades
+mod xml {
+  pub struct Element {
+    pub name: String,
+    attrs: Vec<(String, String)>,
+    nodes: Vec<Node>,
+  }
+
+  enum Node {
+    Element(Element),
+    Text(String),
+  }
+
+  impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+      self.attrs.iter().find(|(attr, _)| attr == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+      self.nodes.iter().filter_map(move |node| match node {
+        Node::Element(element) if element.name == name => Some(element),
+        _ => None,
+      })
+    }
+
+    pub fn child<'a>(&'a self, name: &'a str) -> Option<&'a Element> {
+      self.children(name).next()
+    }
+
+    pub fn text(&self) -> String {
+      self.nodes.iter()
+        .map(|node| match node {
+          Node::Text(text) => text.clone(),
+          Node::Element(element) => element.text(),
+        })
+        .collect()
+    }
+  }
+
+  pub fn parse(text: &str) -> Result<Element, String> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut rest = text;
+    let mut root = None;
+
+    while !rest.is_empty() {
+      let Some(start) = rest.find('<') else {
+        push_text(&mut stack, rest);
+        break;
+      };
+      push_text(&mut stack, &rest[..start]);
+      rest = &rest[start..];
+
+      if let Some(after) = rest.strip_prefix("<![CDATA[") {
+        let end = after.find("]]>").ok_or("CDATA sin cerrar")?;
+        if let Some(parent) = stack.last_mut() {
+          parent.nodes.push(Node::Text(after[..end].to_owned()));
+        }
+        rest = &after[end + 3..];
+      } else if let Some(after) = rest.strip_prefix("<!--") {
+        let end = after.find("-->").ok_or("comentario sin cerrar")?;
+        rest = &after[end + 3..];
+      } else if rest.starts_with("<?") || rest.starts_with("<!") {
+        let end = rest.find('>').ok_or("declaración sin cerrar")?;
+        rest = &rest[end + 1..];
+      } else if let Some(after) = rest.strip_prefix("</") {
+        let end = after.find('>').ok_or("etiqueta sin cerrar")?;
+        let name = after[..end].trim();
+        let element = stack.pop().ok_or_else(|| format!("</{}> sin abrir", name))?;
+        if element.name != name {
+          return Err(format!("se esperaba </{}> y se encontró </{}>", element.name, name));
+        }
+        rest = &after[end + 1..];
+        close(&mut stack, &mut root, element);
+      } else {
+        let end = tag_end(rest).ok_or("etiqueta sin cerrar")?;
+        let tag = &rest[1..end];
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+          Some(tag) => (tag, true),
+          None => (tag, false),
+        };
+        let element = open(tag)?;
+        rest = &rest[end + 1..];
+        if self_closing {
+          close(&mut stack, &mut root, element);
+        } else {
+          stack.push(element);
+        }
+      }
+    }
+
+    if let Some(element) = stack.last() {
+      return Err(format!("<{}> sin cerrar", element.name));
+    }
+    root.ok_or_else(|| String::from("el archivo no tiene elementos"))
+  }
+
+  // Fin de la etiqueta sin contar los '>' dentro de las comillas
+  fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (idx, c) in text.char_indices() {
+      match (quote, c) {
+        (None, '"' | '\'') => quote = Some(c),
+        (Some(open), _) if open == c => quote = None,
+        (None, '>') => return Some(idx),
+        _ => (),
+      }
+    }
+    None
+  }
+
+  fn open(tag: &str) -> Result<Element, String> {
+    let tag = tag.trim();
+    let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+    let name = tag[..name_end].to_owned();
+    if name.is_empty() {
+      return Err(String::from("etiqueta sin nombre"));
+    }
+
+    let mut attrs = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while let Some(eq) = rest.find('=') {
+      let attr = rest[..eq].trim().to_owned();
+      let value = rest[eq + 1..].trim_start();
+      let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'').ok_or_else(|| format!("atributo {} sin comillas", attr))?;
+      let end = value[1..].find(quote).ok_or_else(|| format!("atributo {} sin cerrar", attr))?;
+      attrs.push((attr, decode_entities(&value[1..end + 1])));
+      rest = value[end + 2..].trim_start();
+    }
+    Ok(Element { name, attrs, nodes: Vec::new() })
+  }
+
+  fn close(stack: &mut [Element], root: &mut Option<Element>, element: Element) {
+    match stack.last_mut() {
+      Some(parent) => parent.nodes.push(Node::Element(element)),
+      None => *root = Some(element),
+    }
+  }
+
+  fn push_text(stack: &mut [Element], text: &str) {
+    if let Some(parent) = stack.last_mut() {
+      if !text.is_empty() {
+        parent.nodes.push(Node::Text(decode_entities(text)));
+      }
+    }
+  }
+
+  pub fn decode_entities(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+      result.push_str(&rest[..start]);
+      rest = &rest[start..];
+      let Some(end) = rest.find(';').filter(|end| *end <= 10) else {
+        result.push('&');
+        rest = &rest[1..];
+        continue;
+      };
+      let entity = &rest[1..end];
+      let decoded = match entity {
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "amp" => Some('&'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+          .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+          .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+          .and_then(char::from_u32),
+      };
+      match decoded {
+        Some(c) => {
+          result.push(c);
+          rest = &rest[end + 1..];
+        }
+        None => {
+          result.push('&');
+          rest = &rest[1..];
+        }
+      }
+    }
+    result.push_str(rest);
+    result
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn choices(answers: Option<Answers>) -> (Vec<String>, usize) {
+    match answers {
+      Some(Answers::Choices { options, correct }) => (options, correct),
+      _ => panic!("se esperaban opciones"),
+    }
+  }
+
+  fn correct_text(item: &QuizItem) -> &str {
+    &item.respuestas[&item.respuesta_correcta]
+  }
+
+  fn options(item: &QuizItem) -> Vec<&str> {
+    let mut options: Vec<&str> = item.respuestas.values().map(String::as_str).collect();
+    options.sort();
+    options
+  }
+
+  #[test]
+  fn numeric_choices_are_distinct() {
+    for value in ["0", "1", "2", "3", "-1", "0.5", "10", "3.14", "1000"] {
+      let (options, correct) = choices(numeric_choices(value));
+      assert_eq!(options.len(), 4, "{}", value);
+      let mut unique = options.clone();
+      unique.dedup();
+      assert_eq!(unique.len(), 4, "{}: {:?}", value, options);
+      assert_eq!(options[correct], value);
+      let values: Vec<f64> = options.iter().map(|option| option.parse().unwrap()).collect();
+      assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "{}: {:?}", value, options);
+    }
+    assert!(numeric_choices("muchos").is_none());
+    assert!(numeric_choices("inf").is_none());
+  }
+
+  #[test]
+  fn entities_are_decoded() {
+    assert_eq!(xml::decode_entities("a &lt; b &amp;&amp; c &gt; d"), "a < b && c > d");
+    assert_eq!(xml::decode_entities("&quot;hola&quot; &apos;y&apos;&nbsp;ya"), "\"hola\" 'y' ya");
+    assert_eq!(xml::decode_entities("&#241;and&#xFA;"), "ñandú");
+    assert_eq!(xml::decode_entities("R&D &desconocida; &"), "R&D &desconocida; &");
+  }
+
+  #[test]
+  fn xml_reads_elements_attributes_and_cdata() {
+    let root = xml::parse(r#"<?xml version="1.0"?>
+      <!-- comentario -->
+      <quiz><question type="a &amp; b"><text><![CDATA[<b>uno</b>]]> y &lt;dos&gt;</text><vacio/></question></quiz>"#).unwrap();
+    assert_eq!(root.name, "quiz");
+    let question = root.child("question").unwrap();
+    assert_eq!(question.attr("type"), Some("a & b"));
+    assert_eq!(question.child("text").unwrap().text(), "<b>uno</b> y <dos>");
+    assert!(question.child("vacio").is_some());
+    assert!(question.child("falta").is_none());
+
+    assert!(xml::parse("<quiz><question></quiz>").is_err());
+    assert!(xml::parse("<quiz>").is_err());
+    assert!(xml::parse("solo texto").is_err());
+  }
+
+  #[test]
+  fn strip_html_keeps_lines() {
+    assert_eq!(strip_html("<p>Uno   <b>dos</b></p><p>tres&nbsp;&amp; cuatro</p>"), "Uno dos\ntres & cuatro");
+  }
+
+  const MOODLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<quiz>
+  <question type="category">
+    <category><text>$course$/top/Unidad 2</text></category>
+  </question>
+  <question type="multichoice">
+    <name><text>Capital</text></name>
+    <questiontext format="html"><text><![CDATA[<p>¿Capital de Francia?</p>]]></text></questiontext>
+    <generalfeedback><text>París, desde hace siglos</text></generalfeedback>
+    <single>true</single>
+    <answer fraction="0"><text>Roma</text></answer>
+    <answer fraction="100"><text>París</text></answer>
+    <answer fraction="0"><text>Madrid</text></answer>
+  </question>
+  <question type="truefalse">
+    <name><text>Tierra</text></name>
+    <questiontext><text>La Tierra es plana</text></questiontext>
+    <answer fraction="0"><text>true</text></answer>
+    <answer fraction="100"><text>false</text></answer>
+  </question>
+  <question type="numerical">
+    <name><text>Pi</text></name>
+    <questiontext><text>Valor de pi con dos decimales</text></questiontext>
+    <answer fraction="100"><text>3.14</text></answer>
+    <answer fraction="100"><text>3.1416</text></answer>
+    <answer fraction="50"><text>3</text></answer>
+    <answer fraction="0"><text>2.72</text></answer>
+  </question>
+  <question type="numerical">
+    <name><text>Dos</text></name>
+    <questiontext><text>Uno más uno</text></questiontext>
+    <answer fraction="100"><text>2</text></answer>
+  </question>
+  <question type="shortanswer">
+    <name><text>Corta 1</text></name>
+    <questiontext><text>Color del cielo</text></questiontext>
+    <answer fraction="100"><text>Azul</text></answer>
+  </question>
+  <question type="shortanswer">
+    <name><text>Corta 2</text></name>
+    <questiontext><text>Color del pasto</text></questiontext>
+    <answer fraction="100"><text>Verde</text></answer>
+  </question>
+  <question type="multichoice">
+    <name><text>Varias</text></name>
+    <questiontext><text>Elige dos</text></questiontext>
+    <single>false</single>
+    <answer fraction="50"><text>A</text></answer>
+    <answer fraction="50"><text>B</text></answer>
+  </question>
+  <question type="essay">
+    <name><text>Ensayo</text></name>
+    <questiontext><text>Escribe</text></questiontext>
+  </question>
+</quiz>"#;
+
+  #[test]
+  fn moodle_xml_converts_supported_types() {
+    let report = moodle_xml(MOODLE, "banco").unwrap();
+    assert_eq!(report.items.len(), 6, "{}", report.summary());
+    assert!(report.items.iter().all(|item| item.unidad_tematica == "Unidad 2"));
+
+    let capital = &report.items[0];
+    assert_eq!(capital.pregunta, "¿Capital de Francia?");
+    assert_eq!(correct_text(capital), "París");
+    assert_eq!(options(capital), vec!["Madrid", "París", "Roma"]);
+    assert_eq!(capital.explicacion.as_deref(), Some("París, desde hace siglos"));
+    assert_eq!(capital.tipo_reactivo, ITEM_TYPES[0]);
+
+    let earth = &report.items[1];
+    assert_eq!(earth.tipo_reactivo, ITEM_TYPES[1]);
+    assert_eq!(earth.respuesta_correcta, "F");
+
+    // Las otras respuestas aceptadas no aparecen como opciones incorrectas
+    let pi = &report.items[2];
+    assert_eq!(pi.tipo_reactivo, ITEM_TYPES[2]);
+    assert_eq!(correct_text(pi), "3.14");
+    assert_eq!(options(pi), vec!["2.72", "3.14"]);
+
+    let two = &report.items[3];
+    assert_eq!(correct_text(two), "2");
+    assert_eq!(two.respuestas.len(), 4);
+
+    // Cada respuesta corta usa la de la otra como distractor
+    assert_eq!(options(&report.items[4]), vec!["Azul", "Verde"]);
+    assert_eq!(correct_text(&report.items[4]), "Azul");
+    assert_eq!(correct_text(&report.items[5]), "Verde");
+
+    let skipped: Vec<&str> = report.skipped.iter().map(|skipped| skipped.name.as_str()).collect();
+    assert_eq!(skipped, vec!["Varias", "Ensayo"]);
+  }
+
+  #[test]
+  fn moodle_xml_rejects_other_roots() {
+    assert!(moodle_xml("<html></html>", "banco").is_err());
+  }
+
+  const GIFT: &str = r#"// Comentario
+$CATEGORY: $course$/top/Geografía
+
+::Capital:: ¿Capital de Italia? {
+  ~Madrid#No
+  =Roma#Sí
+  ~París
+  ####Roma es la capital desde 1871
+}
+
+::Mar:: El Mediterráneo es un océano {F}
+
+::Pi:: Valor de pi {#=3.14 =%50%3.1 ~2.72}
+
+::Rango:: Un número entre 1 y 3 {#1..3}
+
+::Hueco:: El sol sale por el {=este} cada mañana
+
+::Hueco 2:: Mañana sale por el {=oeste}
+
+::Escape:: ¿Cuánto es 1\=1? {=Sí ~No\: nunca}
+
+::Ensayo:: Escribe sobre tu día {}
+
+::Parejas:: Empareja {=a -> 1 =b -> 2}
+
+Solo una descripción
+"#;
+
+  #[test]
+  fn gift_converts_supported_types() {
+    let report = gift(GIFT, "banco");
+    assert_eq!(report.items.len(), 7, "{}", report.summary());
+    assert!(report.items.iter().all(|item| item.unidad_tematica == "Geografía"));
+
+    let capital = &report.items[0];
+    assert_eq!(capital.pregunta, "¿Capital de Italia?");
+    assert_eq!(correct_text(capital), "Roma");
+    assert_eq!(options(capital), vec!["Madrid", "París", "Roma"]);
+    assert_eq!(capital.explicacion.as_deref(), Some("Roma es la capital desde 1871"));
+
+    let sea = &report.items[1];
+    assert_eq!(sea.tipo_reactivo, ITEM_TYPES[1]);
+    assert_eq!(sea.respuesta_correcta, "F");
+
+    // El crédito parcial con = también se acepta: no queda como distractor
+    let pi = &report.items[2];
+    assert_eq!(options(pi), vec!["2.72", "3.14"]);
+    assert_eq!(correct_text(pi), "3.14");
+
+    let range = &report.items[3];
+    assert_eq!(correct_text(range), "2");
+    assert_eq!(range.respuestas.len(), 4);
+
+    let blank = &report.items[4];
+    assert_eq!(blank.pregunta, "El sol sale por el _____ cada mañana");
+    assert_eq!(options(blank), vec!["este", "oeste"]);
+
+    let escaped = &report.items[6];
+    assert_eq!(escaped.pregunta, "¿Cuánto es 1=1?");
+    assert_eq!(options(escaped), vec!["No: nunca", "Sí"]);
+
+    let skipped: Vec<(&str, &str)> = report.skipped.iter().map(|skipped| (skipped.name.as_str(), skipped.reason.as_str())).collect();
+    assert_eq!(skipped, vec![
+      ("Ensayo", "ensayo"),
+      ("Parejas", "emparejamiento"),
+      ("Pregunta 10", "sin bloque de respuestas (descripción)"),
+    ]);
+  }
+
+  const AIKEN: &str = "¿Cuál es el planeta más grande?
+A. Marte
+B) Júpiter
+C. Venus
+ANSWER: B
+
+UNIDAD: Física
+Un texto que ocupa
+dos líneas
+A. Uno
+B. Dos
+answer: a
+
+El agua hierve a 100 °C a nivel del mar
+A. Verdadero
+B. Falso
+ANSWER: A
+
+Sin respuesta
+A. Sí
+B. No
+
+Respuesta inexistente
+A. Sí
+B. No
+ANSWER: D
+
+Una sola opción
+A. Única
+ANSWER: A
+
+ANSWER: A
+";
+
+  #[test]
+  fn aiken_reads_blocks_units_and_true_false() {
+    let report = aiken(AIKEN, "banco");
+    assert_eq!(report.items.len(), 3, "{}", report.summary());
+
+    let planet = &report.items[0];
+    assert_eq!(planet.unidad_tematica, "banco");
+    assert_eq!(planet.pregunta, "¿Cuál es el planeta más grande?");
+    assert_eq!(correct_text(planet), "Júpiter");
+    assert_eq!(options(planet), vec!["Júpiter", "Marte", "Venus"]);
+
+    let multiline = &report.items[1];
+    assert_eq!(multiline.unidad_tematica, "Física");
+    assert_eq!(multiline.pregunta, "Un texto que ocupa\ndos líneas");
+    assert_eq!(correct_text(multiline), "Uno");
+
+    let water = &report.items[2];
+    assert_eq!(water.tipo_reactivo, ITEM_TYPES[1]);
+    assert_eq!(water.respuesta_correcta, "V");
+
+    let skipped: Vec<(&str, &str)> = report.skipped.iter().map(|skipped| (skipped.name.as_str(), skipped.reason.as_str())).collect();
+    assert_eq!(skipped, vec![
+      ("Línea 19", "pregunta sin ANSWER"),
+      ("Línea 23", "la respuesta \"D\" no es una de las opciones"),
+      ("Línea 28", "menos de dos opciones"),
+      ("Línea 32", "ANSWER sin pregunta"),
+    ]);
+  }
+
+  #[test]
+  fn aiken_options_need_a_letter_marker_and_text() {
+    assert_eq!(aiken_option("A. Uno"), Some(('A', String::from("Uno"))));
+    assert_eq!(aiken_option("b) dos"), Some(('B', String::from("dos"))));
+    assert_eq!(aiken_option("A.Uno"), None);
+    assert_eq!(aiken_option("A. "), None);
+    assert_eq!(aiken_option("1. Uno"), None);
+    assert!(is_aiken("banco.TXT") && is_aiken("banco.aiken") && !is_aiken("banco.gift"));
+  }
+}
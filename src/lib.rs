@@ -1,2 +1,47 @@
+#[cfg(feature = "http-api")]
+pub mod api;
 pub mod app;
-pub mod components;
\ No newline at end of file
+pub mod assets;
+pub mod classroom;
+pub mod components;
+pub mod csv;
+pub mod daily;
+pub mod duel;
+pub mod editor;
+pub mod exam;
+pub mod import;
+pub mod markup;
+pub mod math;
+pub mod net;
+pub mod profile;
+pub mod raid;
+pub mod results;
+pub mod study;
+// Piezas compartidas por las pruebas de los módulos
+#[cfg(test)]
+pub(crate) mod test_util {
+  use std::time::{Duration, Instant};
+
+  use crate::app::{QuizItem, ITEM_TYPES};
+
+  // Revisa `done` hasta que se cumpla; falla si pasan unos segundos
+  pub fn wait_for(mut done: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !done() {
+      assert!(start.elapsed() < Duration::from_secs(5), "tiempo de espera agotado");
+      std::thread::sleep(Duration::from_millis(10));
+    }
+  }
+
+  // Pregunta de opción múltiple con las respuestas dadas como (clave, texto)
+  pub fn quiz_item(unit: &str, pregunta: &str, answers: &[(&str, &str)], correct: &str) -> QuizItem {
+    QuizItem {
+      unidad_tematica: unit.to_owned(),
+      pregunta: pregunta.to_owned(),
+      respuestas: answers.iter().map(|(key, text)| (key.to_string(), text.to_string())).collect(),
+      respuesta_correcta: correct.to_owned(),
+      tipo_reactivo: ITEM_TYPES[0].to_owned(),
+      ..Default::default()
+    }
+  }
+}
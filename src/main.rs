@@ -1,10 +1,18 @@
-use std::{fs::File, io::Read};
-
 use eframe::egui;
-use calabozos_y_preguntones::app::App;
+use calabozos_y_preguntones::{app::App, assets::{self, AssetError}};
 use egui::FontDefinitions;
 
+#[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result {
+    // --assets <carpeta> reemplaza los archivos incluidos por los de esa carpeta
+    let mut args = std::env::args().skip_while(|arg| arg != "--assets");
+    if args.next().is_some() {
+        assets::set_override_dir(args.next().map(std::path::PathBuf::from));
+    }
+
+    #[cfg(feature = "http-api")]
+    let _api = start_api();
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.,680.])
@@ -15,54 +23,116 @@ fn main() -> eframe::Result {
         ..Default::default()
     };
 
-    let mut fonts = egui::FontDefinitions::default();
-    import_fonts(&mut fonts);
-
     let _ = eframe::run_native(
         "Calabozos y preguntones",
         options,
-        Box::new(|cc| {
-            cc.egui_ctx.set_fonts(fonts);
-            egui_extras::install_image_loaders(&cc.egui_ctx);
-            Ok(Box::new(App::new(cc)))
-        }),
+        Box::new(|cc| Ok(Box::new(create_app(cc)))),
     );
 
     Ok(())
 }
 
-fn import_fonts(fonts: &mut FontDefinitions) {
-    let mut font_data1 = Vec::new();
-    File::open("assets/font/upheavtt.ttf")
-        .expect("Error al abrir el archivo de fuente")
-        .read_to_end(&mut font_data1)
-        .expect("Error al leer el archivo de fuente");
-    
-    let mut font_data2 = Vec::new();
-    File::open("assets/font/VCR_OSD_MONO_1.ttf")
-        .expect("Error al abrir el archivo de fuente")
-        .read_to_end(&mut font_data2)
-        .expect("Error al leer el archivo de fuente");
-
-    fonts.font_data.insert(
-        "fuente_1".to_owned(),
-        egui::FontData::from_owned(font_data1),
-    );
+// En web el juego se dibuja en el canvas de index.html (ver `trunk build`)
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use eframe::wasm_bindgen::JsCast;
 
-    fonts.font_data.insert(
-        "fuente_2".to_owned(),
-        egui::FontData::from_owned(font_data2),
-    );
+    let web_options = eframe::WebOptions::default();
+
+    wasm_bindgen_futures::spawn_local(async {
+        let canvas = eframe::web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id("the_canvas_id"))
+            .and_then(|element| element.dyn_into::<eframe::web_sys::HtmlCanvasElement>().ok())
+            .expect("No se encontró el canvas del juego");
+
+        let _ = eframe::WebRunner::new()
+            .start(
+                canvas,
+                web_options,
+                Box::new(|cc| Ok(Box::new(create_app(cc)))),
+            )
+            .await;
+    });
+}
+
+fn create_app(cc: &eframe::CreationContext<'_>) -> App {
+    let mut fonts = egui::FontDefinitions::default();
+    let font_errors = import_fonts(&mut fonts);
+    cc.egui_ctx.set_fonts(fonts);
+    egui_extras::install_image_loaders(&cc.egui_ctx);
+
+    let mut app = App::new(cc);
+    app.asset_errors.extend(font_errors);
+    app
+}
+
+// Si una fuente no se puede leer se usa la de egui y se avisa en pantalla
+fn import_fonts(fonts: &mut FontDefinitions) -> Vec<AssetError> {
+    let mut errors = Vec::new();
+    let default_fonts = fonts.families[&egui::FontFamily::Proportional].clone();
+
+    match assets::read(assets::TITLE_FONT) {
+        Ok(font_data1) => {
+            fonts.font_data.insert(
+                "fuente_1".to_owned(),
+                egui::FontData::from_owned(font_data1.into_owned()),
+            );
+
+            // Las negritas de las preguntas usan esta familia; los símbolos
+            // que no trae la fuente salen de las de egui
+            let mut family = vec!["fuente_1".to_owned()];
+            family.extend(default_fonts);
+            fonts.families.insert(egui::FontFamily::Name("CustomFont_1".into()), family);
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            // La familia tiene que existir aunque sea con la fuente de egui
+            fonts.families.insert(egui::FontFamily::Name("CustomFont_1".into()), default_fonts);
+            errors.push(e);
+        }
+    }
+
+    match assets::read(assets::TEXT_FONT) {
+        Ok(font_data2) => {
+            fonts.font_data.insert(
+                "fuente_2".to_owned(),
+                egui::FontData::from_owned(font_data2.into_owned()),
+            );
+
+            fonts
+                .families
+                .entry(egui::FontFamily::Proportional)
+                .or_default()
+                .insert(0, "fuente_2".to_owned());
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            errors.push(e);
+        }
+    }
+
+    errors
+}
+
+// --api [puerto] levanta la API HTTP junto con el juego
+#[cfg(feature = "http-api")]
+fn start_api() -> Option<calabozos_y_preguntones::api::ApiServer> {
+    use calabozos_y_preguntones::api::{ApiServer, DEFAULT_PORT};
 
-    fonts
-        .families
-        .entry(egui::FontFamily::Name("CustomFont_1".into()))
-        .or_default()
-        .insert(0, "fuente_1".to_owned());
-
-    fonts
-        .families
-        .entry(egui::FontFamily::Proportional)
-        .or_default()
-        .insert(0, "fuente_2".to_owned());
+    let mut args = std::env::args().skip_while(|arg| arg != "--api");
+    args.next()?;
+    let port = args.next()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(DEFAULT_PORT);
+    match ApiServer::start(port) {
+        Ok(server) => {
+            println!("API HTTP escuchando en http://{}", server.local_addr());
+            Some(server)
+        }
+        Err(e) => {
+            eprintln!("No se pudo iniciar la API HTTP: {}", e);
+            None
+        }
+    }
 }
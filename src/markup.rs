@@ -0,0 +1,320 @@
+use egui::{text::LayoutJob, Align, Color32, FontFamily, FontId, TextFormat};
+
+use crate::math::{self, Script, Span};
+
+// Formato de preguntas, respuestas y explicaciones con un subconjunto de
+// markdown: **negritas**, *cursivas*, `código`, saltos de línea, listas con
+// "- " y tablas sencillas con filas "| a | b |" (si la segunda fila es
+// "|---|---|", la primera es el encabezado). Las fórmulas entre $...$ se
+// siguen leyendo con `math`, y "\*" o "\`" escriben el carácter tal cual.
+
+pub enum Block {
+  Paragraph(String),
+  List(Vec<String>),
+  Table { header: bool, rows: Vec<Vec<String>> },
+}
+
+pub fn blocks(text: &str) -> Vec<Block> {
+  let mut blocks = Vec::new();
+  // Las líneas seguidas forman un párrafo con sus saltos de línea
+  let mut paragraph_open = false;
+  for line in text.lines() {
+    let trimmed = line.trim();
+    if let Some(item) = list_item(trimmed) {
+      match blocks.last_mut() {
+        Some(Block::List(items)) if !paragraph_open => items.push(item.to_owned()),
+        _ => blocks.push(Block::List(vec![item.to_owned()])),
+      }
+      paragraph_open = false;
+    } else if trimmed.len() > 1 && trimmed.starts_with('|') && trimmed.ends_with('|') {
+      match blocks.last_mut() {
+        Some(Block::Table { header, rows }) if is_separator(trimmed) => *header |= rows.len() == 1,
+        Some(Block::Table { rows, .. }) => rows.push(table_cells(trimmed)),
+        _ => blocks.push(Block::Table { header: false, rows: vec![table_cells(trimmed)] }),
+      }
+      paragraph_open = false;
+    } else if trimmed.is_empty() {
+      paragraph_open = false;
+    } else {
+      match blocks.last_mut() {
+        Some(Block::Paragraph(paragraph)) if paragraph_open => {
+          paragraph.push('\n');
+          paragraph.push_str(line.trim_end());
+        }
+        _ => blocks.push(Block::Paragraph(line.trim_end().to_owned())),
+      }
+      paragraph_open = true;
+    }
+  }
+  blocks
+}
+
+fn list_item(line: &str) -> Option<&str> {
+  ["- ", "* ", "• "].iter().find_map(|marker| line.strip_prefix(marker)).map(str::trim)
+}
+
+fn is_separator(line: &str) -> bool {
+  line.contains('-') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+  line[1..line.len() - 1].split('|').map(|cell| cell.trim().to_owned()).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct Styled {
+  pub span: Span,
+  pub bold: bool,
+  pub italic: bool,
+  pub code: bool,
+}
+
+// Texto de un renglón con sus estilos. Un marcador solo abre si tiene con qué
+// cerrarse, así "5 * 3" se queda igual.
+pub fn inline(text: &str) -> Vec<Styled> {
+  let ranges = math::ranges(text);
+  let mut spans: Vec<Styled> = Vec::new();
+  let (mut bold, mut italic) = (false, false);
+
+  let mut idx = 0;
+  while idx < text.len() {
+    let rest = &text[idx..];
+    let previous = text[..idx].chars().next_back();
+    if let Some(&(_, end)) = ranges.iter().find(|(start, _)| *start == idx) {
+      // Las fórmulas toman el estilo del texto que las rodea
+      spans.extend(math::parse(&text[idx + 1..end]).into_iter().map(|span| Styled { span, bold, italic, code: false }));
+      idx = end + 1;
+    } else if let Some(escaped) = rest.strip_prefix('\\').and_then(|rest| rest.chars().next()).filter(|c| matches!(c, '*' | '`' | '$' | '\\')) {
+      push(&mut spans, &escaped.to_string(), bold, italic, false);
+      idx += 1 + escaped.len_utf8();
+    } else if let Some(code) = rest.strip_prefix('`').and_then(|rest| rest.find('`').map(|end| &rest[..end])).filter(|code| !code.is_empty()) {
+      push(&mut spans, code, bold, italic, true);
+      idx += code.len() + 2;
+    } else if rest.starts_with("**") && toggles(bold, previous, &rest[2..], "**") {
+      bold = !bold;
+      idx += 2;
+    } else if rest.starts_with('*') && toggles(italic, previous, &rest[1..], "*") {
+      italic = !italic;
+      idx += 1;
+    } else {
+      let c = rest.chars().next().unwrap_or_default();
+      push(&mut spans, &c.to_string(), bold, italic, false);
+      idx += c.len_utf8();
+    }
+  }
+  spans
+}
+
+fn push(spans: &mut Vec<Styled>, text: &str, bold: bool, italic: bool, code: bool) {
+  match spans.last_mut() {
+    Some(last) if last.span.script == Script::Normal && last.span.depth == 0 && (last.bold, last.italic, last.code) == (bold, italic, code) => {
+      last.span.text.push_str(text);
+    }
+    _ => spans.push(Styled {
+      span: Span { text: text.to_owned(), script: Script::Normal, depth: 0 },
+      bold,
+      italic,
+      code,
+    }),
+  }
+}
+
+// Abre si lo que sigue no empieza con espacio y hay un cierre más adelante;
+// cierra si lo anterior no es espacio
+fn toggles(open: bool, previous: Option<char>, rest: &str, marker: &str) -> bool {
+  if open {
+    return previous.is_some_and(|c| !c.is_whitespace());
+  }
+  let starts_word = rest.chars().next().is_some_and(|c| !c.is_whitespace());
+  starts_word && rest.find(marker).is_some_and(|end| end > 0 && !rest[..end].ends_with(char::is_whitespace))
+}
+
+// Un bloque como líneas de texto: las listas con viñeta y las celdas de las
+// tablas separadas por " | "
+fn lines(block: &Block) -> Vec<String> {
+  match block {
+    Block::Paragraph(paragraph) => paragraph.lines().map(String::from).collect(),
+    Block::List(items) => items.iter().map(|item| format!("• {}", item)).collect(),
+    Block::Table { rows, .. } => rows.iter().map(|row| row.join(" | ")).collect(),
+  }
+}
+
+// Negritas con la fuente de los títulos, cursivas inclinadas y código en
+// monoespaciada con fondo; los índices se achican en cada nivel
+pub fn job(spans: &[Styled], size: f32, color: Color32) -> LayoutJob {
+  let mut job = LayoutJob::default();
+  for styled in spans {
+    let span = &styled.span;
+    let size = size * 0.7f32.powi(span.depth.min(2) as i32);
+    let font_id = if styled.code {
+      FontId::monospace(size * 0.9)
+    } else if styled.bold {
+      FontId::new(size, FontFamily::Name("CustomFont_1".into()))
+    } else {
+      FontId::proportional(size)
+    };
+    let valign = match span.script {
+      Script::Normal => Align::Center,
+      Script::Super => Align::TOP,
+      Script::Sub => Align::BOTTOM,
+    };
+    job.append(&span.text, 0.0, TextFormat {
+      font_id,
+      color,
+      italics: styled.italic,
+      background: if styled.code { Color32::from_black_alpha(90) } else { Color32::TRANSPARENT },
+      valign,
+      ..Default::default()
+    });
+  }
+  job
+}
+
+// Todo el texto en un solo LayoutJob, para botones y etiquetas sencillas
+pub fn layout_job(text: &str, size: f32, color: Color32) -> LayoutJob {
+  let mut spans = Vec::new();
+  for line in blocks(text).iter().flat_map(lines) {
+    if !spans.is_empty() {
+      push(&mut spans, "\n", false, false, false);
+    }
+    spans.extend(inline(&line));
+  }
+  job(&spans, size, color)
+}
+
+// Sin formato, para la terminal y el PDF
+pub fn to_plain(text: &str, unicode: bool) -> String {
+  blocks(text).iter()
+    .map(|block| {
+      lines(block).iter()
+        .map(|line| math::plain(&inline(line).into_iter().map(|styled| styled.span).collect::<Vec<_>>(), unicode))
+        .collect::<Vec<_>>()
+        .join("\n")
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+pub fn to_html(text: &str) -> String {
+  let mut html = String::new();
+  for block in blocks(text) {
+    match block {
+      Block::Paragraph(paragraph) => {
+        let lines: Vec<String> = paragraph.lines().map(inline_html).collect();
+        html.push_str(&format!("<p>{}</p>", lines.join("<br>")));
+      }
+      Block::List(items) => {
+        html.push_str("<ul>");
+        for item in items {
+          html.push_str(&format!("<li>{}</li>", inline_html(&item)));
+        }
+        html.push_str("</ul>");
+      }
+      Block::Table { header, rows } => {
+        html.push_str("<table>");
+        for (idx, row) in rows.iter().enumerate() {
+          let tag = if header && idx == 0 { "th" } else { "td" };
+          html.push_str("<tr>");
+          for cell in row {
+            html.push_str(&format!("<{}>{}</{}>", tag, inline_html(cell), tag));
+          }
+          html.push_str("</tr>");
+        }
+        html.push_str("</table>");
+      }
+    }
+  }
+  html
+}
+
+fn inline_html(text: &str) -> String {
+  inline(text).iter()
+    .map(|styled| {
+      let mut html = escape_html(&styled.span.text);
+      match styled.span.script {
+        Script::Normal => {}
+        Script::Super => html = format!("<sup>{}</sup>", html),
+        Script::Sub => html = format!("<sub>{}</sub>", html),
+      }
+      for (on, tag) in [(styled.code, "code"), (styled.italic, "i"), (styled.bold, "b")] {
+        if on {
+          html = format!("<{}>{}</{}>", tag, html, tag);
+        }
+      }
+      html
+    })
+    .collect()
+}
+
+pub fn escape_html(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SAMPLE: &str = "Texto **fuerte** y *suave*\nsegunda\n\n- uno\n- `dos`\n\n| A | B |\n|---|---|\n| 1 | $x^2$ |";
+
+  fn describe(block: &Block) -> String {
+    match block {
+      Block::Paragraph(paragraph) => format!("p: {}", paragraph),
+      Block::List(items) => format!("ul: {}", items.join(", ")),
+      Block::Table { header, rows } => format!("table {}: {:?}", header, rows),
+    }
+  }
+
+  fn styles(text: &str) -> Vec<(String, bool, bool, bool)> {
+    inline(text).into_iter().map(|styled| (styled.span.text, styled.bold, styled.italic, styled.code)).collect()
+  }
+
+  #[test]
+  fn blocks_split_paragraphs_lists_and_tables() {
+    let described: Vec<String> = blocks(SAMPLE).iter().map(describe).collect();
+    assert_eq!(described, vec![
+      "p: Texto **fuerte** y *suave*\nsegunda",
+      "ul: uno, `dos`",
+      "table true: [[\"A\", \"B\"], [\"1\", \"$x^2$\"]]",
+    ]);
+
+    // Sin separador no hay encabezado; un párrafo corta la lista
+    let described: Vec<String> = blocks("| a |\n| b |\n- x\nmás\n- y").iter().map(describe).collect();
+    assert_eq!(described, vec!["table false: [[\"a\"], [\"b\"]]", "ul: x", "p: más", "ul: y"]);
+  }
+
+  #[test]
+  fn inline_styles() {
+    assert_eq!(styles("a **b** *c* `d`"), vec![
+      (String::from("a "), false, false, false),
+      (String::from("b"), true, false, false),
+      (String::from(" "), false, false, false),
+      (String::from("c"), false, true, false),
+      (String::from(" "), false, false, false),
+      (String::from("d"), false, false, true),
+    ]);
+  }
+
+  #[test]
+  fn markers_without_pair_or_escaped_stay_as_text() {
+    assert_eq!(to_plain("5 * 3 y 2 * 4", true), "5 * 3 y 2 * 4");
+    assert_eq!(to_plain("**sin cierre", true), "**sin cierre");
+    assert_eq!(to_plain("\\*no\\* \\`ni\\` \\$esto", true), "*no* `ni` $esto");
+  }
+
+  #[test]
+  fn plain_text_keeps_structure_and_formulas() {
+    assert_eq!(to_plain(SAMPLE, true), "Texto fuerte y suave\nsegunda\n• uno\n• dos\nA | B\n1 | x²");
+    assert_eq!(to_plain("$x^2$", false), "x^2");
+  }
+
+  #[test]
+  fn html_output_is_escaped() {
+    assert_eq!(
+      to_html(SAMPLE),
+      "<p>Texto <b>fuerte</b> y <i>suave</i><br>segunda</p>\
+       <ul><li>uno</li><li><code>dos</code></li></ul>\
+       <table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>x<sup>2</sup></td></tr></table>"
+    );
+    assert_eq!(to_html("**a <b> & \"c\"**"), "<p><b>a &lt;b&gt; &amp; &quot;c&quot;</b></p>");
+  }
+}
@@ -0,0 +1,395 @@
+// Fórmulas dentro de preguntas y respuestas con un subconjunto de LaTeX entre
+// signos de dólar, p. ej. "$X_{n+1} = (aX_n + c) \bmod m$". Se admiten
+// subíndices, superíndices, \frac, \sqrt, letras griegas y los operadores más
+// comunes. Fuera de los signos el texto queda igual; "\$" es un signo de
+// dólar normal y un "$" sin pareja también se deja tal cual. El formato del
+// resto del texto (negritas, listas...) está en `markup`.
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Script {
+  Normal,
+  Super,
+  Sub,
+}
+
+#[derive(Clone, Debug)]
+pub struct Span {
+  pub text: String,
+  pub script: Script,
+  // Qué tan anidado está el índice; cada nivel se dibuja más chico
+  pub depth: u8,
+}
+
+pub fn spans(text: &str) -> Vec<Span> {
+  let mut parser = Parser::default();
+  let mut offset = 0;
+  for (start, end) in ranges(text) {
+    parser.push(&unescape_dollars(&text[offset..start]), Script::Normal, 0);
+    let mut chars = text[start + 1..end].chars().peekable();
+    parser.parse_group(&mut chars, Script::Normal, 0, false);
+    offset = end + 1;
+  }
+  parser.push(&unescape_dollars(&text[offset..]), Script::Normal, 0);
+  parser.spans
+}
+
+// Una fórmula sin los signos de dólar
+pub fn parse(formula: &str) -> Vec<Span> {
+  let mut parser = Parser::default();
+  parser.parse_group(&mut formula.chars().peekable(), Script::Normal, 0, false);
+  parser.spans
+}
+
+// Versión en texto plano para la terminal y el PDF. Con `unicode` los
+// índices que se pueden escribir con caracteres de índice (₀₁₂, ⁰¹²...) se
+// usan; los demás quedan como _(...) y ^(...).
+pub fn to_plain(text: &str, unicode: bool) -> String {
+  plain(&spans(text), unicode)
+}
+
+pub fn plain(spans: &[Span], unicode: bool) -> String {
+  let mut plain = String::new();
+  let mut idx = 0;
+  while idx < spans.len() {
+    let span = &spans[idx];
+    idx += 1;
+    // Fracción apilada: numerador, "⁄" y denominador
+    if let [numerator, slash, denominator, ..] = &spans[idx - 1..] {
+      if numerator.script == Script::Super && denominator.script == Script::Sub && slash.text.starts_with('⁄') {
+        let mapped = (map_chars(&numerator.text, SUPERSCRIPTS), map_chars(&denominator.text, SUBSCRIPTS));
+        match mapped {
+          (Some(numerator), Some(denominator)) if unicode => plain.push_str(&format!("{}⁄{}", numerator, denominator)),
+          _ => plain.push_str(&format!("{}/{}", numerator.text, denominator.text)),
+        }
+        plain.push_str(&slash.text['⁄'.len_utf8()..]);
+        idx += 2;
+        continue;
+      }
+    }
+
+    let (marker, table) = match span.script {
+      Script::Normal => {
+        plain.push_str(&span.text);
+        continue;
+      }
+      Script::Super => ('^', SUPERSCRIPTS),
+      Script::Sub => ('_', SUBSCRIPTS),
+    };
+    match map_chars(&span.text, table).filter(|_| unicode) {
+      Some(mapped) => plain.push_str(&mapped),
+      None if span.text.chars().count() == 1 => {
+        plain.push(marker);
+        plain.push_str(&span.text);
+      }
+      None => plain.push_str(&format!("{}({})", marker, span.text)),
+    }
+  }
+  plain
+}
+
+fn map_chars(text: &str, table: &[(char, char)]) -> Option<String> {
+  text.chars()
+    .map(|c| table.iter().find(|(from, _)| *from == c).map(|(_, to)| *to))
+    .collect()
+}
+
+const SUPERSCRIPTS: &[(char, char)] = &[
+  ('0', '⁰'), ('1', '¹'), ('2', '²'), ('3', '³'), ('4', '⁴'), ('5', '⁵'), ('6', '⁶'), ('7', '⁷'), ('8', '⁸'), ('9', '⁹'),
+  ('+', '⁺'), ('-', '⁻'), ('=', '⁼'), ('(', '⁽'), (')', '⁾'), ('n', 'ⁿ'), ('i', 'ⁱ'),
+];
+
+const SUBSCRIPTS: &[(char, char)] = &[
+  ('0', '₀'), ('1', '₁'), ('2', '₂'), ('3', '₃'), ('4', '₄'), ('5', '₅'), ('6', '₆'), ('7', '₇'), ('8', '₈'), ('9', '₉'),
+  ('+', '₊'), ('-', '₋'), ('=', '₌'), ('(', '₍'), (')', '₎'),
+  ('a', 'ₐ'), ('e', 'ₑ'), ('i', 'ᵢ'), ('j', 'ⱼ'), ('k', 'ₖ'), ('n', 'ₙ'), ('o', 'ₒ'), ('x', 'ₓ'),
+];
+
+// Posiciones (inicio, fin) de cada par de "$" sin escapar
+pub fn ranges(text: &str) -> Vec<(usize, usize)> {
+  let dollars: Vec<usize> = text.char_indices()
+    .filter(|&(idx, c)| c == '$' && !text[..idx].ends_with('\\'))
+    .map(|(idx, _)| idx)
+    .collect();
+  dollars.chunks_exact(2).map(|pair| (pair[0], pair[1])).collect()
+}
+
+fn unescape_dollars(text: &str) -> String {
+  text.replace("\\$", "$")
+}
+
+// Cuántos elementos pueden ir uno dentro de otro ({{...}}, x^{y^{...}},
+// \frac{\frac...}); más allá el resto se deja como texto para no agotar la pila
+const MAX_NESTING: u16 = 32;
+
+#[derive(Default)]
+struct Parser {
+  spans: Vec<Span>,
+  nesting: u16,
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+impl Parser {
+  fn push(&mut self, text: &str, script: Script, depth: u8) {
+    if text.is_empty() {
+      return;
+    }
+    match self.spans.last_mut() {
+      Some(last) if last.script == script && last.depth == depth => last.text.push_str(text),
+      _ => self.spans.push(Span { text: text.to_owned(), script, depth }),
+    }
+  }
+
+  fn atom(&self, chars: &mut Chars, script: Script, depth: u8) -> Vec<Span> {
+    let mut inner = Parser { spans: Vec::new(), nesting: self.nesting };
+    inner.parse_atom(chars, script, depth);
+    inner.spans
+  }
+
+  // Agrega entre paréntesis si tiene operaciones sueltas: √(n+1), pero √2
+  fn extend_grouped(&mut self, spans: Vec<Span>, script: Script, depth: u8) {
+    let grouped = needs_parens(&spans);
+    if grouped {
+      self.push("(", script, depth);
+    }
+    for span in spans {
+      self.push(&span.text, span.script, span.depth);
+    }
+    if grouped {
+      self.push(")", script, depth);
+    }
+  }
+
+  // Lee hasta la llave que cierra el grupo (si `closed`) o hasta el final
+  fn parse_group(&mut self, chars: &mut Chars, script: Script, depth: u8, closed: bool) {
+    while let Some(&c) = chars.peek() {
+      if c == '}' && closed {
+        chars.next();
+        return;
+      }
+      self.parse_atom(chars, script, depth);
+    }
+  }
+
+  // Un solo elemento: un carácter, un grupo entre llaves o un comando
+  fn parse_atom(&mut self, chars: &mut Chars, script: Script, depth: u8) {
+    let Some(c) = chars.next() else { return };
+    if self.nesting >= MAX_NESTING {
+      let rest: String = std::iter::once(c).chain(chars.by_ref()).collect();
+      self.push(&rest, script, depth);
+      return;
+    }
+    self.nesting += 1;
+    match c {
+      '{' => self.parse_group(chars, script, depth, true),
+      '_' => self.parse_atom(chars, Script::Sub, depth.saturating_add(1)),
+      '^' => self.parse_atom(chars, Script::Super, depth.saturating_add(1)),
+      '~' => self.push(" ", script, depth),
+      '\\' => self.parse_command(chars, script, depth),
+      _ => self.push(c.encode_utf8(&mut [0; 4]), script, depth),
+    }
+    self.nesting -= 1;
+  }
+
+  fn parse_command(&mut self, chars: &mut Chars, script: Script, depth: u8) {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek().filter(|c| c.is_ascii_alphabetic()) {
+      name.push(c);
+      chars.next();
+    }
+    if name.is_empty() {
+      // Comandos de un solo símbolo: \, \; \{ \} \$ \\ ...
+      match chars.next() {
+        Some(',' | ';' | ':' | ' ' | '!') => self.push(" ", script, depth),
+        Some('\\') => self.push("\n", script, depth),
+        Some(c) => self.push(c.encode_utf8(&mut [0; 4]), script, depth),
+        None => self.push("\\", script, depth),
+      }
+      return;
+    }
+
+    match name.as_str() {
+      "frac" | "dfrac" | "tfrac" => {
+        let numerator = self.atom(chars, script, depth);
+        let denominator = self.atom(chars, script, depth);
+        // Las fracciones sencillas van apiladas (¹⁄₂); las demás en línea
+        // con paréntesis donde hagan falta: (a+b)/c
+        if is_simple(&numerator) && is_simple(&denominator) {
+          self.push(&plain_text(&numerator), Script::Super, depth.saturating_add(1));
+          self.push("⁄", script, depth);
+          self.push(&plain_text(&denominator), Script::Sub, depth.saturating_add(1));
+        } else {
+          self.extend_grouped(numerator, script, depth);
+          self.push("/", script, depth);
+          self.extend_grouped(denominator, script, depth);
+        }
+      }
+      "sqrt" => {
+        let radicand = self.atom(chars, script, depth);
+        self.push("√", script, depth);
+        self.extend_grouped(radicand, script, depth);
+      }
+      "text" | "mathrm" | "mathbf" | "mathit" | "operatorname" => self.parse_atom(chars, script, depth),
+      "left" | "right" | "big" | "Big" | "bigg" | "Bigg" => {
+        // El delimitador que sigue se dibuja normal; "\left." no dibuja nada
+        if chars.peek() == Some(&'.') {
+          chars.next();
+        }
+      }
+      "mod" | "bmod" => {
+        // Se respetan los espacios que ya haya alrededor
+        let spaced = self.spans.last().is_some_and(|span| span.text.ends_with(' '));
+        self.push(if spaced { "mod " } else { " mod " }, script, depth);
+        while chars.next_if_eq(&' ').is_some() {}
+      }
+      "quad" | "qquad" => self.push("  ", script, depth),
+      _ => match symbol(&name) {
+        Some(symbol) => self.push(symbol, script, depth),
+        // Funciones como \ln o \sin y comandos desconocidos: el nombre tal cual
+        None => self.push(&name, script, depth),
+      },
+    }
+  }
+}
+
+fn plain_text(spans: &[Span]) -> String {
+  spans.iter().map(|span| span.text.as_str()).collect()
+}
+
+fn is_simple(spans: &[Span]) -> bool {
+  matches!(spans, [span] if span.script == Script::Normal && span.text.chars().count() <= 3 && !needs_parens(spans))
+}
+
+// Operadores fuera de paréntesis en el texto normal (no en los índices)
+fn needs_parens(spans: &[Span]) -> bool {
+  let mut level = 0;
+  for span in spans {
+    for c in span.text.chars() {
+      match c {
+        '(' => level += 1,
+        ')' => level -= 1,
+        '+' | '-' | ' ' | '·' | '×' | '=' | '/' if level == 0 && span.script == Script::Normal => return true,
+        _ => (),
+      }
+    }
+  }
+  false
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+  let symbol = match name {
+    "alpha" => "α",
+    "beta" => "β",
+    "gamma" => "γ",
+    "delta" => "δ",
+    "epsilon" | "varepsilon" => "ε",
+    "zeta" => "ζ",
+    "eta" => "η",
+    "theta" | "vartheta" => "θ",
+    "iota" => "ι",
+    "kappa" => "κ",
+    "lambda" => "λ",
+    "mu" => "μ",
+    "nu" => "ν",
+    "xi" => "ξ",
+    "pi" => "π",
+    "rho" => "ρ",
+    "sigma" => "σ",
+    "tau" => "τ",
+    "upsilon" => "υ",
+    "phi" | "varphi" => "φ",
+    "chi" => "χ",
+    "psi" => "ψ",
+    "omega" => "ω",
+    "Gamma" => "Γ",
+    "Delta" => "Δ",
+    "Theta" => "Θ",
+    "Lambda" => "Λ",
+    "Xi" => "Ξ",
+    "Pi" => "Π",
+    "Sigma" => "Σ",
+    "Phi" => "Φ",
+    "Psi" => "Ψ",
+    "Omega" => "Ω",
+    "sum" => "∑",
+    "prod" => "∏",
+    "int" => "∫",
+    "partial" => "∂",
+    "infty" => "∞",
+    "cdot" => "·",
+    "times" => "×",
+    "div" => "÷",
+    "pm" => "±",
+    "le" | "leq" => "≤",
+    "ge" | "geq" => "≥",
+    "ne" | "neq" => "≠",
+    "approx" => "≈",
+    "equiv" => "≡",
+    "to" | "rightarrow" => "→",
+    "leftarrow" => "←",
+    "Rightarrow" | "implies" => "⇒",
+    "in" => "∈",
+    "ldots" | "dots" | "cdots" => "…",
+    _ => return None,
+  };
+  Some(symbol)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn summary(spans: &[Span]) -> Vec<(&str, Script, u8)> {
+    spans.iter().map(|span| (span.text.as_str(), span.script, span.depth)).collect()
+  }
+
+  #[test]
+  fn scripts_keep_their_depth() {
+    assert_eq!(summary(&spans("Sea $X_{n+1}$ y $e^{x^2}$")), vec![
+      ("Sea X", Script::Normal, 0),
+      ("n+1", Script::Sub, 1),
+      (" y e", Script::Normal, 0),
+      ("x", Script::Super, 1),
+      ("2", Script::Super, 2),
+    ]);
+  }
+
+  #[test]
+  fn plain_text_with_and_without_unicode() {
+    let lcg = "$X_{n+1} = (aX_n + c) \\bmod m$";
+    assert_eq!(to_plain(lcg, true), "Xₙ₊₁ = (aXₙ + c) mod m");
+    assert_eq!(to_plain(lcg, false), "X_(n+1) = (aX_n + c) mod m");
+    assert_eq!(to_plain("$x^{10}$, $x^{ab}$ y $x_k$", true), "x¹⁰, x^(ab) y xₖ");
+  }
+
+  #[test]
+  fn fractions_roots_and_symbols() {
+    assert_eq!(to_plain("$\\frac{1}{2}$ y $\\frac{a+b}{c}$", true), "¹⁄₂ y (a+b)/c");
+    assert_eq!(to_plain("$\\frac12$", false), "1/2");
+    assert_eq!(to_plain("$\\sqrt{n+1} + \\sqrt2$", true), "√(n+1) + √2");
+    assert_eq!(to_plain("$\\alpha \\le \\pi \\cdot r^2$", true), "α ≤ π · r²");
+    assert_eq!(to_plain("$\\ln x$ y $\\desconocido$", true), "ln x y desconocido");
+  }
+
+  #[test]
+  fn dollars_outside_formulas() {
+    assert_eq!(to_plain("Cuesta \\$5", true), "Cuesta $5");
+    assert_eq!(to_plain("Un $ suelto", true), "Un $ suelto");
+    assert_eq!(ranges("$a$ \\$ $b$ $"), vec![(0, 2), (7, 9)]);
+  }
+
+  // Una fórmula escrita a propósito muy anidada no debe agotar la pila
+  #[test]
+  fn deep_nesting_is_capped() {
+    let braces = format!("${}x{}$", "{".repeat(100_000), "}".repeat(100_000));
+    assert!(to_plain(&braces, true).contains('x'));
+
+    let powers = format!("${}2$", "x^".repeat(100_000));
+    let spans = spans(&powers);
+    assert!(spans.iter().all(|span| span.depth as u16 <= MAX_NESTING));
+    assert!(plain_text(&spans).ends_with('2'));
+
+    let fractions = format!("${}1{}$", "\\frac{".repeat(10_000), "}{2}".repeat(10_000));
+    assert!(!to_plain(&fractions, true).is_empty());
+  }
+}
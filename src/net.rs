@@ -0,0 +1,327 @@
+use std::{
+  collections::HashMap,
+  io::{self, BufRead, BufReader, Write},
+  marker::PhantomData,
+  net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs, UdpSocket},
+  sync::{atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver, Sender}, Arc, Mutex},
+  thread,
+  time::Duration,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+// Transporte sencillo: un mensaje JSON por línea sobre TCP. Cada conexión
+// tiene su propio hilo lector que deja los mensajes en un canal, así la
+// interfaz solo tiene que revisar el canal en cada cuadro. En el servidor
+// cada conexión también tiene un hilo escritor, para que un alumno lento no
+// detenga los cuadros del profesor.
+
+pub type ClientId = u32;
+
+pub enum ServerEvent<M> {
+  Connected(ClientId),
+  Message(ClientId, M),
+  Disconnected(ClientId),
+}
+
+pub enum ClientEvent<M> {
+  Message(M),
+  Disconnected,
+}
+
+// El socket se guarda para poder cerrarlo; los mensajes salen por el canal
+// hacia el hilo escritor
+struct Connection {
+  stream: TcpStream,
+  outgoing: Sender<Vec<u8>>,
+}
+
+type Connections = Arc<Mutex<HashMap<ClientId, Connection>>>;
+
+pub struct Server<In, Out> {
+  clients: Connections,
+  events: Receiver<ServerEvent<In>>,
+  shutdown: Arc<AtomicBool>,
+  local_addr: SocketAddr,
+  _out: PhantomData<Out>,
+}
+
+impl<In, Out> Server<In, Out>
+where
+  In: DeserializeOwned + Send + 'static,
+  Out: Serialize,
+{
+  pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    let local_addr = listener.local_addr()?;
+
+    let clients: Connections = Arc::default();
+    let shutdown = Arc::new(AtomicBool::new(false));
+    let (tx, events) = mpsc::channel();
+
+    let accept_clients = Arc::clone(&clients);
+    let accept_shutdown = Arc::clone(&shutdown);
+    thread::spawn(move || {
+      let mut next_id: ClientId = 1;
+      while !accept_shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+          Ok((stream, _)) => {
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_nodelay(true);
+            let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+            let (Ok(reader), Ok(writer)) = (stream.try_clone(), stream.try_clone()) else { continue };
+            let id = next_id;
+            next_id += 1;
+            let outgoing = spawn_server_writer(writer);
+            accept_clients.lock().unwrap().insert(id, Connection { stream, outgoing });
+            if tx.send(ServerEvent::Connected(id)).is_err() {
+              break;
+            }
+            spawn_server_reader(id, reader, tx.clone(), Arc::clone(&accept_clients));
+          }
+          // WouldBlock: no hay conexiones pendientes
+          Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+      }
+    });
+
+    Ok(Server {
+      clients,
+      events,
+      shutdown,
+      local_addr,
+      _out: PhantomData,
+    })
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.local_addr
+  }
+
+  pub fn poll(&self) -> Vec<ServerEvent<In>> {
+    self.events.try_iter().collect()
+  }
+
+  // No bloquea: el mensaje queda en la cola del hilo escritor de ese cliente
+  pub fn send_to(&self, id: ClientId, msg: &Out) {
+    let Ok(line) = encode(msg) else { return };
+    let mut clients = self.clients.lock().unwrap();
+    if let Some(connection) = clients.get(&id) {
+      if connection.outgoing.send(line).is_err() {
+        let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+        clients.remove(&id);
+      }
+    }
+  }
+
+  pub fn broadcast(&self, msg: &Out) {
+    let Ok(line) = encode(msg) else { return };
+    for connection in self.clients.lock().unwrap().values() {
+      let _ = connection.outgoing.send(line.clone());
+    }
+  }
+}
+
+impl<In, Out> Drop for Server<In, Out> {
+  fn drop(&mut self) {
+    self.shutdown.store(true, Ordering::Relaxed);
+    for connection in self.clients.lock().unwrap().values() {
+      let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+    }
+  }
+}
+
+fn spawn_server_reader<In>(
+  id: ClientId,
+  stream: TcpStream,
+  tx: Sender<ServerEvent<In>>,
+  clients: Connections,
+) where
+  In: DeserializeOwned + Send + 'static,
+{
+  thread::spawn(move || {
+    for line in BufReader::new(stream).lines() {
+      let Ok(line) = line else { break };
+      // Los mensajes mal formados se ignoran en lugar de tumbar la conexión
+      if let Ok(msg) = serde_json::from_str(&line) {
+        if tx.send(ServerEvent::Message(id, msg)).is_err() {
+          break;
+        }
+      }
+    }
+    clients.lock().unwrap().remove(&id);
+    let _ = tx.send(ServerEvent::Disconnected(id));
+  });
+}
+
+// Escribe en orden lo que llega por el canal. Si el alumno deja de leer, el
+// tiempo de espera de escritura cierra la conexión y el lector avisa la
+// desconexión; el hilo termina cuando se quita el cliente del mapa.
+fn spawn_server_writer(mut stream: TcpStream) -> Sender<Vec<u8>> {
+  let (tx, rx) = mpsc::channel::<Vec<u8>>();
+  thread::spawn(move || {
+    for line in rx {
+      if stream.write_all(&line).is_err() {
+        let _ = stream.shutdown(std::net::Shutdown::Both);
+        break;
+      }
+    }
+  });
+  tx
+}
+
+pub struct Client<In, Out> {
+  stream: TcpStream,
+  events: Receiver<ClientEvent<In>>,
+  _out: PhantomData<Out>,
+}
+
+impl<In, Out> Client<In, Out>
+where
+  In: DeserializeOwned + Send + 'static,
+  Out: Serialize,
+{
+  pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+    let addr = addr.to_socket_addrs()?
+      .next()
+      .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "dirección inválida"))?;
+    let stream = TcpStream::connect_timeout(&addr, Duration::from_secs(5))?;
+    stream.set_nodelay(true)?;
+    let reader = stream.try_clone()?;
+    let (tx, events) = mpsc::channel();
+
+    thread::spawn(move || {
+      for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else { break };
+        if let Ok(msg) = serde_json::from_str(&line) {
+          if tx.send(ClientEvent::Message(msg)).is_err() {
+            return;
+          }
+        }
+      }
+      let _ = tx.send(ClientEvent::Disconnected);
+    });
+
+    Ok(Client {
+      stream,
+      events,
+      _out: PhantomData,
+    })
+  }
+
+  pub fn poll(&self) -> Vec<ClientEvent<In>> {
+    self.events.try_iter().collect()
+  }
+
+  pub fn send(&mut self, msg: &Out) -> io::Result<()> {
+    write_message(&mut self.stream, msg)
+  }
+}
+
+impl<In, Out> Drop for Client<In, Out> {
+  fn drop(&mut self) {
+    let _ = self.stream.shutdown(std::net::Shutdown::Both);
+  }
+}
+
+fn encode<T: Serialize>(msg: &T) -> io::Result<Vec<u8>> {
+  let mut line = serde_json::to_vec(msg)?;
+  line.push(b'\n');
+  Ok(line)
+}
+
+fn write_message<T: Serialize>(stream: &mut TcpStream, msg: &T) -> io::Result<()> {
+  stream.write_all(&encode(msg)?)
+}
+
+// IP de esta máquina en la red local, para mostrarla a los alumnos. No se
+// envía ningún paquete: solo se consulta la ruta que usaría el sistema.
+pub fn local_ip() -> Option<std::net::IpAddr> {
+  let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+  socket.connect("8.8.8.8:80").ok()?;
+  socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::wait_for;
+  use serde_json::{json, Value};
+  use std::time::Instant;
+
+  #[test]
+  fn json_lines_round_trip() {
+    let server: Server<Value, Value> = Server::bind("127.0.0.1:0").unwrap();
+    let mut client: Client<Value, Value> = Client::connect(server.local_addr()).unwrap();
+    client.send(&json!({ "tipo": "Join", "name": "Ana" })).unwrap();
+
+    let mut id = None;
+    let mut received = Vec::new();
+    wait_for(|| {
+      for event in server.poll() {
+        match event {
+          ServerEvent::Connected(client_id) => id = Some(client_id),
+          ServerEvent::Message(client_id, msg) => received.push((client_id, msg)),
+          ServerEvent::Disconnected(_) => panic!("desconexión inesperada"),
+        }
+      }
+      !received.is_empty()
+    });
+    let id = id.unwrap();
+    assert_eq!(received, vec![(id, json!({ "tipo": "Join", "name": "Ana" }))]);
+
+    server.send_to(id, &json!({ "tipo": "Welcome" }));
+    server.broadcast(&json!({ "tipo": "End" }));
+    let mut messages = Vec::new();
+    wait_for(|| {
+      messages.extend(client.poll().into_iter().filter_map(|event| match event {
+        ClientEvent::Message(msg) => Some(msg),
+        ClientEvent::Disconnected => None,
+      }));
+      messages.len() == 2
+    });
+    assert_eq!(messages, vec![json!({ "tipo": "Welcome" }), json!({ "tipo": "End" })]);
+
+    drop(client);
+    wait_for(|| server.poll().iter().any(|event| matches!(event, ServerEvent::Disconnected(client_id) if *client_id == id)));
+  }
+
+  #[test]
+  fn malformed_lines_are_ignored() {
+    let server: Server<Value, Value> = Server::bind("127.0.0.1:0").unwrap();
+    let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+    stream.write_all(b"esto no es json\n{\"ok\":true}\n").unwrap();
+
+    let mut messages = Vec::new();
+    wait_for(|| {
+      messages.extend(server.poll().into_iter().filter_map(|event| match event {
+        ServerEvent::Message(_, msg) => Some(msg),
+        _ => None,
+      }));
+      !messages.is_empty()
+    });
+    assert_eq!(messages, vec![json!({ "ok": true })]);
+  }
+
+  // Un cliente que no lee no debe detener los envíos del servidor
+  #[test]
+  fn send_to_does_not_block_on_slow_clients() {
+    let server: Server<Value, Value> = Server::bind("127.0.0.1:0").unwrap();
+    let _stream = TcpStream::connect(server.local_addr()).unwrap();
+    let mut id = None;
+    wait_for(|| {
+      id = server.poll().into_iter().find_map(|event| match event {
+        ServerEvent::Connected(id) => Some(id),
+        _ => None,
+      });
+      id.is_some()
+    });
+
+    let big = json!({ "texto": "x".repeat(64 * 1024) });
+    let start = Instant::now();
+    for _ in 0..200 {
+      server.send_to(id.unwrap(), &big);
+    }
+    assert!(start.elapsed() < Duration::from_millis(500));
+  }
+}
@@ -0,0 +1,161 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+const PROFILES_FILE: &str = "perfiles.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyResult {
+  pub date: String,
+  pub completed: bool,
+  pub won: bool,
+  pub correct_answers: u16,
+  pub wrong_answers: u16,
+  pub best_hit: u8,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profile {
+  pub name: String,
+  pub daily_results: Vec<DailyResult>,
+  // Preguntas falladas que no se han vuelto a contestar bien, para la guía de estudio
+  #[serde(default)]
+  pub missed: Vec<String>,
+}
+
+impl Profile {
+  pub fn daily_result(&self, date: &str) -> Option<&DailyResult> {
+    self.daily_results.iter().find(|result| result.date == date)
+  }
+
+  pub fn daily_result_mut(&mut self, date: &str) -> Option<&mut DailyResult> {
+    self.daily_results.iter_mut().find(|result| result.date == date)
+  }
+
+  // Devuelve true si la lista de falladas cambió
+  pub fn record_answer(&mut self, pregunta: &str, correct: bool) -> bool {
+    let idx = self.missed.iter().position(|missed| missed == pregunta);
+    match (idx, correct) {
+      (None, false) => self.missed.push(pregunta.to_owned()),
+      (Some(idx), true) => {
+        self.missed.remove(idx);
+      }
+      _ => return false,
+    }
+    true
+  }
+}
+
+// El nombre se escribe a mano en el menú: "Ana", " ana" y "ANA  " son el
+// mismo jugador, si no el reto diario se podría repetir cambiando el nombre
+fn name_key(name: &str) -> String {
+  name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Profiles {
+  pub profiles: Vec<Profile>,
+}
+
+impl Profiles {
+  pub fn load() -> Self {
+    read_profiles()
+      .and_then(|json_str| serde_json::from_str(&json_str).ok())
+      .unwrap_or_default()
+  }
+
+  pub fn save(&self) -> std::io::Result<()> {
+    let json_str = serde_json::to_string_pretty(self)?;
+    write_profiles(&json_str)
+  }
+
+  pub fn get(&self, name: &str) -> Option<&Profile> {
+    let key = name_key(name);
+    self.profiles.iter().find(|profile| name_key(&profile.name) == key)
+  }
+
+  // Actualiza las preguntas falladas del jugador y guarda si cambiaron
+  pub fn record_answer(&mut self, name: &str, pregunta: &str, correct: bool) {
+    if self.get_or_create(name).record_answer(pregunta, correct) {
+      let _ = self.save();
+    }
+  }
+
+  pub fn get_or_create(&mut self, name: &str) -> &mut Profile {
+    let key = name_key(name);
+    match self.profiles.iter().position(|profile| name_key(&profile.name) == key) {
+      Some(idx) => &mut self.profiles[idx],
+      None => {
+        self.profiles.push(Profile {
+          name: name.trim().to_owned(),
+          ..Default::default()
+        });
+        self.profiles.last_mut().unwrap()
+      }
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_profiles() -> Option<String> {
+  std::fs::read_to_string(profiles_path()).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_profiles(json_str: &str) -> std::io::Result<()> {
+  let path = profiles_path();
+  if let Some(dir) = path.parent() {
+    std::fs::create_dir_all(dir)?;
+  }
+  std::fs::write(path, json_str)
+}
+
+// En el navegador los perfiles se guardan en el localStorage de la página
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+  web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_profiles() -> Option<String> {
+  local_storage()?.get_item(PROFILES_FILE).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_profiles(json_str: &str) -> std::io::Result<()> {
+  let storage = local_storage()
+    .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::Unsupported, "localStorage no disponible"))?;
+  storage.set_item(PROFILES_FILE, json_str)
+    .map_err(|_| std::io::Error::other("no se pudo guardar en localStorage"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn data_dir() -> PathBuf {
+  let base = std::env::var_os("APPDATA")
+    .map(PathBuf::from)
+    .or_else(|| std::env::var_os("XDG_DATA_HOME").map(PathBuf::from))
+    .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share")))
+    .unwrap_or_else(|| PathBuf::from("."));
+  base.join("calabozos_y_preguntones")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn profiles_path() -> PathBuf {
+  data_dir().join(PROFILES_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn names_differing_in_case_or_spaces_share_a_profile() {
+    let mut profiles = Profiles::default();
+    profiles.get_or_create("  Ana  López ").missed.push(String::from("¿2 + 2?"));
+
+    assert_eq!(profiles.get_or_create("ana lópez").missed.len(), 1);
+    assert!(profiles.get("ANA LÓPEZ").is_some());
+    assert_eq!(profiles.profiles.len(), 1);
+    assert_eq!(profiles.profiles[0].name, "Ana  López");
+  }
+}
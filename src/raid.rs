@@ -0,0 +1,448 @@
+use std::{collections::HashMap, io, net::{IpAddr, SocketAddr}};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::{app::{HealthStatus, StreakState}, net::{Client, ClientEvent, ClientId, Server, ServerEvent}};
+
+pub const DEFAULT_PORT: u16 = 7879;
+const LOG_LINES: usize = 6;
+const BOSS_HP_PER_PLAYER: f32 = 1.5;
+
+// Sincronización: el anfitrión es la única fuente de verdad. Cada jugador
+// contesta sus propias preguntas y solo reporta si acertó; el anfitrión tira
+// el dado, aplica el daño y reparte una foto completa del estado a todos.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum RaidClientMessage {
+  Join { name: String },
+  Answered { correct: bool },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "tipo")]
+pub enum RaidHostMessage {
+  Welcome { player_id: u32 },
+  State { snapshot: RaidSnapshot },
+  // Para quien llega cuando la raid ya empezó
+  Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RaidStatus {
+  Lobby,
+  Running,
+  Victory,
+  Defeat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaidPlayer {
+  pub id: u32,
+  pub name: String,
+  pub health: f32,
+  pub correct: u16,
+  pub wrong: u16,
+  pub streak: u16,
+  pub damage_dealt: u32,
+  pub connected: bool,
+}
+
+impl RaidPlayer {
+  pub fn is_alive(&self) -> bool {
+    self.health > 0.0
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaidSnapshot {
+  pub status: RaidStatus,
+  pub boss_health: f32,
+  pub players: Vec<RaidPlayer>,
+  pub shared_streak: bool,
+  pub team_streak: u16,
+  pub log: Vec<String>,
+}
+
+impl RaidSnapshot {
+  pub fn player(&self, id: u32) -> Option<&RaidPlayer> {
+    self.players.iter().find(|player| player.id == id)
+  }
+
+  // Multiplicador que se aplicaría al siguiente acierto de este jugador
+  pub fn streak_for(&self, id: u32) -> StreakState {
+    if self.shared_streak {
+      return StreakState::from_streak(self.team_streak);
+    }
+    self.player(id)
+      .map(|player| StreakState::from_streak(player.streak))
+      .unwrap_or_default()
+  }
+}
+
+pub struct RaidSettings {
+  pub port: u16,
+  pub address: String,
+  pub shared_streak: bool,
+  pub error: Option<String>,
+}
+
+impl Default for RaidSettings {
+  fn default() -> Self {
+    RaidSettings {
+      port: DEFAULT_PORT,
+      address: format!("127.0.0.1:{}", DEFAULT_PORT),
+      shared_streak: true,
+      error: None,
+    }
+  }
+}
+
+pub struct RaidHost {
+  server: Server<RaidClientMessage, RaidHostMessage>,
+  clients: HashMap<ClientId, u32>,
+  boss: HealthStatus,
+  snapshot: RaidSnapshot,
+  rng: StdRng,
+  pub lan_ip: Option<IpAddr>,
+}
+
+impl RaidHost {
+  pub const HOST_PLAYER_ID: u32 = 0;
+
+  pub fn start(port: u16, host_name: &str, shared_streak: bool) -> io::Result<Self> {
+    let mut host = RaidHost {
+      server: Server::bind(("0.0.0.0", port))?,
+      clients: HashMap::new(),
+      boss: HealthStatus::default(),
+      snapshot: RaidSnapshot {
+        status: RaidStatus::Lobby,
+        boss_health: 1.0,
+        players: Vec::new(),
+        shared_streak,
+        team_streak: 0,
+        log: Vec::new(),
+      },
+      rng: StdRng::from_entropy(),
+      lan_ip: crate::net::local_ip(),
+    };
+    host.add_player(Self::HOST_PLAYER_ID, host_name);
+    Ok(host)
+  }
+
+  pub fn local_addr(&self) -> SocketAddr {
+    self.server.local_addr()
+  }
+
+  pub fn snapshot(&self) -> &RaidSnapshot {
+    &self.snapshot
+  }
+
+  pub fn update(&mut self) {
+    for event in self.server.poll() {
+      match event {
+        ServerEvent::Connected(_) => (),
+        ServerEvent::Message(id, RaidClientMessage::Join { name }) => {
+          if self.clients.contains_key(&id) {
+            continue;
+          }
+          // Solo se aceptan jugadores nuevos en la sala de espera
+          if self.snapshot.status != RaidStatus::Lobby {
+            let reason = String::from("La raid ya comenzó; espera a la siguiente");
+            self.server.send_to(id, &RaidHostMessage::Rejected { reason });
+            continue;
+          }
+          let player_id = id;
+          self.clients.insert(id, player_id);
+          self.add_player(player_id, &name);
+          self.server.send_to(id, &RaidHostMessage::Welcome { player_id });
+          self.broadcast();
+        }
+        ServerEvent::Message(id, RaidClientMessage::Answered { correct }) => {
+          if let Some(&player_id) = self.clients.get(&id) {
+            self.apply_answer(player_id, correct);
+          }
+        }
+        ServerEvent::Disconnected(id) => {
+          if let Some(player_id) = self.clients.remove(&id) {
+            if let Some(player) = self.snapshot.players.iter_mut().find(|player| player.id == player_id) {
+              player.connected = false;
+              let name = player.name.clone();
+              self.push_log(format!("{} se desconectó", name));
+            }
+            self.check_end();
+            self.broadcast();
+          }
+        }
+      }
+    }
+  }
+
+  fn add_player(&mut self, id: u32, name: &str) {
+    self.snapshot.players.push(RaidPlayer {
+      id,
+      name: name.to_owned(),
+      health: 1.0,
+      correct: 0,
+      wrong: 0,
+      streak: 0,
+      damage_dealt: 0,
+      connected: true,
+    });
+  }
+
+  pub fn begin(&mut self) {
+    if self.snapshot.status != RaidStatus::Lobby {
+      return;
+    }
+    self.boss.enemy_max_hp = BOSS_HP_PER_PLAYER * self.snapshot.players.len() as f32;
+    self.snapshot.status = RaidStatus::Running;
+    self.push_log(String::from("¡Comienza la raid!"));
+    self.broadcast();
+  }
+
+  pub fn local_answer(&mut self, correct: bool) {
+    self.apply_answer(Self::HOST_PLAYER_ID, correct);
+  }
+
+  fn apply_answer(&mut self, player_id: u32, correct: bool) {
+    if self.snapshot.status != RaidStatus::Running {
+      return;
+    }
+    let streak = self.snapshot.streak_for(player_id);
+    let shared_streak = self.snapshot.shared_streak;
+    let Some(player) = self.snapshot.players.iter_mut().find(|player| player.id == player_id) else { return };
+    if !player.is_alive() {
+      return;
+    }
+
+    let line = if correct {
+      let roll = self.rng.gen_range(1..=10);
+      let damage = roll * streak.multiplier();
+      self.boss.damage_enemy(damage as f32 / 100.0);
+      player.correct += 1;
+      player.streak += 1;
+      player.damage_dealt += damage as u32;
+      match streak {
+        StreakState::NoStreak => format!("{} sacó {} y golpeó al jefe", player.name, roll),
+        _ => format!("{} sacó {} x{} = {} de daño", player.name, roll, streak.multiplier(), damage),
+      }
+    } else {
+      // Los errores solo lastiman a quien se equivocó
+      player.health = (player.health - self.boss.enemy_damage).clamp(0.0, 1.0);
+      player.wrong += 1;
+      player.streak = 0;
+      if player.is_alive() {
+        format!("{} falló y recibió un golpe", player.name)
+      } else {
+        format!("{} cayó en combate", player.name)
+      }
+    };
+
+    if shared_streak {
+      self.snapshot.team_streak = if correct { self.snapshot.team_streak + 1 } else { 0 };
+    }
+    self.snapshot.boss_health = self.boss.enemy_health;
+    self.push_log(line);
+    self.check_end();
+    self.broadcast();
+  }
+
+  fn check_end(&mut self) {
+    if self.snapshot.status != RaidStatus::Running {
+      return;
+    }
+    if self.snapshot.boss_health == 0.0 {
+      self.snapshot.status = RaidStatus::Victory;
+      self.push_log(String::from("¡El jefe fue derrotado!"));
+    } else if !self.snapshot.players.iter().any(|player| player.is_alive() && player.connected) {
+      self.snapshot.status = RaidStatus::Defeat;
+      self.push_log(String::from("El equipo fue derrotado"));
+    }
+  }
+
+  fn push_log(&mut self, line: String) {
+    self.snapshot.log.push(line);
+    if self.snapshot.log.len() > LOG_LINES {
+      self.snapshot.log.remove(0);
+    }
+  }
+
+  fn broadcast(&self) {
+    self.server.broadcast(&RaidHostMessage::State { snapshot: self.snapshot.clone() });
+  }
+}
+
+pub struct RaidClient {
+  client: Client<RaidHostMessage, RaidClientMessage>,
+  pub player_id: Option<u32>,
+  pub snapshot: Option<RaidSnapshot>,
+  pub connected: bool,
+  pub rejected: Option<String>,
+}
+
+impl RaidClient {
+  pub fn join(address: &str, name: &str) -> io::Result<Self> {
+    let mut client = Client::connect(address)?;
+    client.send(&RaidClientMessage::Join { name: name.to_owned() })?;
+    Ok(RaidClient {
+      client,
+      player_id: None,
+      snapshot: None,
+      connected: true,
+      rejected: None,
+    })
+  }
+
+  pub fn update(&mut self) {
+    for event in self.client.poll() {
+      match event {
+        ClientEvent::Message(RaidHostMessage::Welcome { player_id }) => self.player_id = Some(player_id),
+        ClientEvent::Message(RaidHostMessage::State { snapshot }) => {
+          // Los estados que llegan antes del rechazo no son de este jugador
+          if self.rejected.is_none() {
+            self.snapshot = Some(snapshot);
+          }
+        }
+        ClientEvent::Message(RaidHostMessage::Rejected { reason }) => {
+          self.rejected = Some(reason);
+          self.snapshot = None;
+        }
+        ClientEvent::Disconnected => self.connected = false,
+      }
+    }
+  }
+
+  pub fn answer(&mut self, correct: bool) {
+    if self.client.send(&RaidClientMessage::Answered { correct }).is_err() {
+      self.connected = false;
+    }
+  }
+}
+
+pub enum Raid {
+  Inactive,
+  Host(Box<RaidHost>),
+  Client(RaidClient),
+}
+
+impl Raid {
+  pub fn update(&mut self) {
+    match self {
+      Raid::Inactive => (),
+      Raid::Host(host) => host.update(),
+      Raid::Client(client) => client.update(),
+    }
+  }
+
+  pub fn snapshot(&self) -> Option<&RaidSnapshot> {
+    match self {
+      Raid::Inactive => None,
+      Raid::Host(host) => Some(host.snapshot()),
+      Raid::Client(client) => client.snapshot.as_ref(),
+    }
+  }
+
+  pub fn local_player_id(&self) -> Option<u32> {
+    match self {
+      Raid::Inactive => None,
+      Raid::Host(_) => Some(RaidHost::HOST_PLAYER_ID),
+      Raid::Client(client) => client.player_id,
+    }
+  }
+
+  pub fn answer(&mut self, correct: bool) {
+    match self {
+      Raid::Inactive => (),
+      Raid::Host(host) => host.local_answer(correct),
+      Raid::Client(client) => client.answer(correct),
+    }
+  }
+
+  // Por qué el anfitrión no dejó entrar a este jugador
+  pub fn rejection(&self) -> Option<&str> {
+    match self {
+      Raid::Client(client) => client.rejected.as_deref(),
+      _ => None,
+    }
+  }
+
+  pub fn is_connected(&self) -> bool {
+    match self {
+      Raid::Client(client) => client.connected,
+      _ => true,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::wait_for;
+
+  fn join(host: &mut RaidHost, name: &str) -> RaidClient {
+    let address = format!("127.0.0.1:{}", host.local_addr().port());
+    RaidClient::join(&address, name).unwrap()
+  }
+
+  #[test]
+  fn answers_from_clients_resolve_on_the_host() {
+    let mut host = RaidHost::start(0, "Anfitrión", true).unwrap();
+    let mut client = join(&mut host, "Ana");
+    wait_for(|| {
+      host.update();
+      client.update();
+      client.snapshot.as_ref().is_some_and(|snapshot| snapshot.players.len() == 2)
+    });
+    let player_id = client.player_id.unwrap();
+
+    host.begin();
+    client.answer(true);
+    wait_for(|| {
+      host.update();
+      host.snapshot().player(player_id).unwrap().correct == 1
+    });
+    assert!(host.snapshot().boss_health < 1.0);
+    assert_eq!(host.snapshot().team_streak, 1);
+
+    // Un error solo lastima a quien lo cometió y corta la racha del equipo
+    client.answer(false);
+    wait_for(|| {
+      host.update();
+      host.snapshot().player(player_id).unwrap().wrong == 1
+    });
+    assert!(host.snapshot().player(player_id).unwrap().health < 1.0);
+    assert_eq!(host.snapshot().player(RaidHost::HOST_PLAYER_ID).unwrap().health, 1.0);
+    assert_eq!(host.snapshot().team_streak, 0);
+
+    wait_for(|| {
+      client.update();
+      client.snapshot.as_ref().is_some_and(|snapshot| snapshot.status == RaidStatus::Running && snapshot.team_streak == 0)
+    });
+  }
+
+  #[test]
+  fn boss_falls_after_enough_hits() {
+    let mut host = RaidHost::start(0, "Anfitrión", false).unwrap();
+    host.begin();
+    for _ in 0..100 {
+      host.local_answer(true);
+    }
+    assert_eq!(host.snapshot().status, RaidStatus::Victory);
+    assert_eq!(host.snapshot().boss_health, 0.0);
+  }
+
+  #[test]
+  fn late_players_are_rejected() {
+    let mut host = RaidHost::start(0, "Anfitrión", true).unwrap();
+    host.begin();
+    let mut client = join(&mut host, "Beto");
+    wait_for(|| {
+      host.update();
+      client.update();
+      client.rejected.is_some()
+    });
+    assert!(client.snapshot.is_none());
+    assert_eq!(host.snapshot().players.len(), 1);
+  }
+}
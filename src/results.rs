@@ -0,0 +1,114 @@
+use serde::Serialize;
+
+use crate::{app::QuizItem, csv};
+
+// Detalle de cada respuesta de una partida, para que el profesor pueda
+// juntar y calificar las sesiones de práctica de sus alumnos. Los campos
+// de daño y vida quedan vacíos en los modos que no los tienen.
+#[derive(Clone, Serialize)]
+pub struct AnswerRecord {
+  #[serde(rename = "numero")]
+  pub number: usize,
+  #[serde(rename = "jugador")]
+  pub player: String,
+  #[serde(rename = "unidad")]
+  pub unit: String,
+  #[serde(rename = "pregunta")]
+  pub question: String,
+  #[serde(rename = "clave")]
+  pub key: Option<String>,
+  #[serde(rename = "respuesta_correcta")]
+  pub correct_key: String,
+  #[serde(rename = "correcta")]
+  pub correct: bool,
+  #[serde(rename = "tiempo_ms")]
+  pub time_ms: u64,
+  #[serde(rename = "dano")]
+  pub damage: Option<u32>,
+  #[serde(rename = "vida_heroe")]
+  pub hero_health: Option<u8>,
+  #[serde(rename = "vida_enemigo")]
+  pub enemy_health: Option<u8>,
+}
+
+impl AnswerRecord {
+  // `key` es None si se acabó el tiempo sin contestar
+  pub fn new(number: usize, player: &str, item: &QuizItem, key: Option<&str>, time_ms: u64) -> Self {
+    AnswerRecord {
+      number,
+      player: player.to_owned(),
+      unit: item.unidad_tematica.clone(),
+      question: item.pregunta.clone(),
+      key: key.map(String::from),
+      correct_key: item.respuesta_correcta.clone(),
+      correct: key == Some(item.respuesta_correcta.as_str()),
+      time_ms,
+      damage: None,
+      hero_health: None,
+      enemy_health: None,
+    }
+  }
+}
+
+pub fn to_csv(records: &[AnswerRecord]) -> String {
+  let header = [
+    "Número", "Jugador", "Unidad Temática", "Pregunta", "Clave elegida", "Respuesta correcta",
+    "Correcta", "Tiempo (ms)", "Daño", "Vida del héroe (%)", "Vida del enemigo (%)",
+  ].map(String::from);
+  let optional = |value: Option<u32>| value.map(|value| value.to_string()).unwrap_or_default();
+
+  let mut text = String::from(csv::BOM);
+  csv::write_row(&mut text, &header);
+  for record in records {
+    csv::write_row(&mut text, &[
+      record.number.to_string(),
+      record.player.clone(),
+      record.unit.clone(),
+      record.question.clone(),
+      record.key.clone().unwrap_or_default(),
+      record.correct_key.clone(),
+      String::from(if record.correct { "sí" } else { "no" }),
+      record.time_ms.to_string(),
+      optional(record.damage),
+      optional(record.hero_health.map(u32::from)),
+      optional(record.enemy_health.map(u32::from)),
+    ]);
+  }
+  text
+}
+
+// Resumen de la sesión con el detalle de las respuestas
+pub fn to_json(mode: &str, date: &str, records: &[AnswerRecord]) -> String {
+  let correct = records.iter().filter(|record| record.correct).count();
+  let json = serde_json::json!({
+    "modo": mode,
+    "fecha": date,
+    "preguntas": records.len(),
+    "correctas": correct,
+    "incorrectas": records.len() - correct,
+    "respuestas": records,
+  });
+  serde_json::to_string_pretty(&json).unwrap_or_default()
+}
+
+// Escribe `<jugador>_<fecha>_<hora>.csv` y `.json` en la carpeta de
+// resultados y devuelve la ruta sin extensión
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(player: &str, mode: &str, records: &[AnswerRecord]) -> Result<std::path::PathBuf, String> {
+  use std::time::{SystemTime, UNIX_EPOCH};
+
+  let date = crate::daily::DailyDate::today().to_string();
+  let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or_default() % 86_400;
+  let name: String = player.chars()
+    .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+    .collect();
+  let dir = crate::profile::data_dir().join("resultados");
+  std::fs::create_dir_all(&dir).map_err(|e| format!("No se pudo crear {}: {}", dir.display(), e))?;
+
+  let base = dir.join(format!("{}_{}_{:02}{:02}{:02}", name, date, secs / 3600, secs / 60 % 60, secs % 60));
+  for (extension, contents) in [("csv", to_csv(records)), ("json", to_json(mode, &date, records))] {
+    let path = base.with_extension(extension);
+    std::fs::write(&path, contents).map_err(|e| format!("No se pudo guardar {}: {}", path.display(), e))?;
+  }
+  Ok(base)
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use crate::{app::QuizItem, markup::{self, escape_html as escape}};
+
+// Guía de estudio para repasar sin el juego: las preguntas agrupadas por
+// unidad temática con la respuesta correcta y la explicación. Se genera como
+// HTML autocontenido o como PDF sencillo, sin dependencias externas.
+
+pub fn by_unit(items: &[QuizItem]) -> BTreeMap<&str, Vec<&QuizItem>> {
+  let mut units: BTreeMap<&str, Vec<&QuizItem>> = BTreeMap::new();
+  for item in items {
+    units.entry(item.unidad_tematica.as_str()).or_default().push(item);
+  }
+  units
+}
+
+fn sorted_answers(item: &QuizItem) -> Vec<(&String, &String)> {
+  let mut answers: Vec<(&String, &String)> = item.respuestas.iter().collect();
+  answers.sort();
+  answers
+}
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:50em;margin:2em auto;padding:0 1em;color:#222}\
+h1{border-bottom:2px solid #444}h2{margin-top:2em;color:#5a2d0c}\
+.pregunta{break-inside:avoid;margin-bottom:1.5em}.pregunta p{margin:.3em 0}\
+ol{list-style:none;padding-left:1em;margin:.3em 0}.correcta{font-weight:bold;color:#1d6b1d}\
+.explicacion{background:#f3f0e8;padding:.4em .8em;border-left:3px solid #b0a070}\
+.meta{color:#777;font-size:.85em}li p{display:inline}.pregunta ul{margin:.3em 0}\
+table{border-collapse:collapse;margin:.3em 0}th,td{border:1px solid #bbb;padding:.2em .6em}\
+code{background:#eee;padding:0 .2em}";
+
+pub fn html(title: &str, items: &[QuizItem]) -> String {
+  let mut html = format!(
+    "<!DOCTYPE html>\n<html lang=\"es\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+    escape(title), STYLE, escape(title),
+  );
+  html.push_str(&format!("<p class=\"meta\">{} preguntas</p>\n", items.len()));
+  for (unit, unit_items) in by_unit(items) {
+    html.push_str(&format!("<h2>{}</h2>\n", escape(unit)));
+    for (idx, item) in unit_items.iter().enumerate() {
+      html.push_str("<div class=\"pregunta\">\n");
+      // El número va dentro del primer párrafo de la pregunta
+      let question = markup::to_html(&item.pregunta);
+      match question.strip_prefix("<p>") {
+        Some(rest) => html.push_str(&format!("<p><b>{}.</b> {}\n", idx + 1, rest)),
+        None => html.push_str(&format!("<p><b>{}.</b></p>{}\n", idx + 1, question)),
+      }
+      if let Some(dificultad) = &item.dificultad {
+        html.push_str(&format!("<p class=\"meta\">{} · {}</p>\n", escape(&item.tipo_reactivo), escape(dificultad)));
+      } else {
+        html.push_str(&format!("<p class=\"meta\">{}</p>\n", escape(&item.tipo_reactivo)));
+      }
+      html.push_str("<ol>\n");
+      for (key, answer) in sorted_answers(item) {
+        if *key == item.respuesta_correcta {
+          html.push_str(&format!("<li class=\"correcta\">✔ {}) {}</li>\n", escape(key), markup::to_html(answer)));
+        } else {
+          html.push_str(&format!("<li>{}) {}</li>\n", escape(key), markup::to_html(answer)));
+        }
+      }
+      html.push_str("</ol>\n");
+      if let Some(explicacion) = &item.explicacion {
+        html.push_str(&format!("<div class=\"explicacion\">{}</div>\n", markup::to_html(explicacion)));
+      }
+      html.push_str("</div>\n");
+    }
+  }
+  html.push_str("</body>\n</html>\n");
+  html
+}
+
+// PDF tamaño carta con las fuentes estándar Helvetica, que cualquier visor
+// trae; los símbolos que no existen en su codificación salen como '?'
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 56.0;
+
+#[derive(Clone, Copy)]
+enum Font {
+  Regular,
+  Bold,
+}
+
+struct Line {
+  font: Font,
+  size: f32,
+  indent: f32,
+  text: String,
+  // Espacio antes de la línea
+  gap: f32,
+}
+
+pub fn pdf(title: &str, items: &[QuizItem]) -> Vec<u8> {
+  let mut lines = Vec::new();
+  let mut push = |font: Font, size: f32, indent: f32, gap: f32, text: &str| {
+    for (idx, text) in wrap(text, size, PAGE_WIDTH - 2.0 * MARGIN - indent).into_iter().enumerate() {
+      lines.push(Line { font, size, indent, text, gap: if idx == 0 { gap } else { 0.0 } });
+    }
+  };
+
+  push(Font::Bold, 20.0, 0.0, 0.0, title);
+  push(Font::Regular, 10.0, 0.0, 4.0, &format!("{} preguntas", items.len()));
+  for (unit, unit_items) in by_unit(items) {
+    push(Font::Bold, 15.0, 0.0, 18.0, unit);
+    for (idx, item) in unit_items.iter().enumerate() {
+      push(Font::Bold, 11.0, 0.0, 12.0, &format!("{}. {}", idx + 1, markup::to_plain(&item.pregunta, false)));
+      for (key, answer) in sorted_answers(item) {
+        if *key == item.respuesta_correcta {
+          push(Font::Bold, 11.0, 16.0, 2.0, &format!("{}) {}  (correcta)", key, markup::to_plain(answer, false)));
+        } else {
+          push(Font::Regular, 11.0, 16.0, 2.0, &format!("{}) {}", key, markup::to_plain(answer, false)));
+        }
+      }
+      if let Some(explicacion) = &item.explicacion {
+        push(Font::Regular, 10.0, 16.0, 4.0, &format!("Explicación: {}", markup::to_plain(explicacion, false)));
+      }
+    }
+  }
+
+  // Reparte las líneas en páginas
+  let mut pages: Vec<String> = Vec::new();
+  let mut content = String::new();
+  let mut y = PAGE_HEIGHT - MARGIN;
+  for line in lines {
+    let height = line.size * 1.3;
+    if y - line.gap - height < MARGIN && !content.is_empty() {
+      pages.push(std::mem::take(&mut content));
+      y = PAGE_HEIGHT - MARGIN;
+    } else {
+      y -= line.gap;
+    }
+    y -= height;
+    let font = match line.font {
+      Font::Regular => "F1",
+      Font::Bold => "F2",
+    };
+    content.push_str(&format!("BT /{} {} Tf {:.1} {:.1} Td ({}) Tj ET\n", font, line.size, MARGIN + line.indent, y, pdf_string(&line.text)));
+  }
+  pages.push(content);
+
+  // Objetos: 1 catálogo, 2 páginas, 3 y 4 fuentes, y luego cada página con su contenido
+  let mut objects: Vec<Vec<u8>> = Vec::new();
+  let kids: Vec<String> = (0..pages.len()).map(|idx| format!("{} 0 R", 5 + 2 * idx)).collect();
+  objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+  objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes());
+  for font in ["Helvetica", "Helvetica-Bold"] {
+    objects.push(format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", font).into_bytes());
+  }
+  for (idx, content) in pages.iter().enumerate() {
+    objects.push(format!(
+      "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+      PAGE_WIDTH, PAGE_HEIGHT, 6 + 2 * idx,
+    ).into_bytes());
+    let content = win_ansi(content);
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend(content);
+    stream.extend(b"\nendstream");
+    objects.push(stream);
+  }
+
+  let mut pdf = b"%PDF-1.4\n".to_vec();
+  let mut offsets = Vec::new();
+  for (idx, object) in objects.iter().enumerate() {
+    offsets.push(pdf.len());
+    pdf.extend(format!("{} 0 obj\n", idx + 1).into_bytes());
+    pdf.extend(object);
+    pdf.extend(b"\nendobj\n");
+  }
+  let xref = pdf.len();
+  pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+  for offset in offsets {
+    pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+  }
+  pdf.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).into_bytes());
+  pdf
+}
+
+// Ancho aproximado de Helvetica: medio punto por carácter y tamaño
+fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+  let max_chars = ((width / (size * 0.5)) as usize).max(10);
+  let mut lines = Vec::new();
+  for paragraph in text.lines() {
+    let mut line = String::new();
+    for word in paragraph.split_whitespace() {
+      if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > max_chars {
+        lines.push(std::mem::take(&mut line));
+      }
+      if !line.is_empty() {
+        line.push(' ');
+      }
+      line.push_str(word);
+    }
+    lines.push(line);
+  }
+  if lines.is_empty() {
+    lines.push(String::new());
+  }
+  lines
+}
+
+fn pdf_string(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('(', "\\(").replace(')', "\\)")
+}
+
+// Latin-1 coincide con WinAnsi salvo por unos cuantos signos de puntuación
+fn win_ansi(text: &str) -> Vec<u8> {
+  text.chars()
+    .map(|c| match c {
+      '€' => 0x80,
+      '…' => 0x85,
+      '‘' => 0x91,
+      '’' => 0x92,
+      '“' => 0x93,
+      '”' => 0x94,
+      '•' => 0x95,
+      '–' => 0x96,
+      '—' => 0x97,
+      c if (c as u32) < 0x80 || (0xa0..=0xff).contains(&(c as u32)) => c as u8,
+      _ => b'?',
+    })
+    .collect()
+}